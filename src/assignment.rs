@@ -0,0 +1,107 @@
+// src/assignment.rs
+
+// Assigns players to slots for the highest total. Slots that are
+// interchangeable (same position and weight) are grouped, so the search is
+// over how many of each group are still open rather than over every ordering.
+//
+// `scores[player][group]` is the player's score in that group, or None if
+// they can't play there; `counts[group]` is how many slots the group has.
+// Returns the group each player takes and the total, or None if not every
+// player can be placed.
+pub fn best_assignment(scores: &[Vec<Option<f64>>], counts: &[usize]) -> Option<(Vec<usize>, f64)> {
+    let mut open = counts.to_vec();
+    let mut search = AssignmentSearch::new(scores, counts);
+
+    let total = search.best(0, &mut open);
+    if total == f64::MIN {
+        return None;
+    }
+
+    let choices = search.choices(&mut open);
+    (choices.len() == scores.len()).then_some((choices, total))
+}
+
+// Exhaustive search over which group each player takes, memoized on
+// (player index, open slots per group).
+struct AssignmentSearch<'a> {
+    scores: &'a [Vec<Option<f64>>],
+    strides: Vec<usize>,
+    states: usize,
+    memo: Vec<Option<(f64, usize)>>,
+}
+
+impl<'a> AssignmentSearch<'a> {
+    fn new(scores: &'a [Vec<Option<f64>>], counts: &[usize]) -> Self {
+        let mut strides = Vec::with_capacity(counts.len());
+        let mut states = 1;
+        for count in counts {
+            strides.push(states);
+            states *= count + 1;
+        }
+
+        let memo = vec![None; states * (scores.len() + 1)];
+        Self { scores, strides, states, memo }
+    }
+
+    fn key(&self, index: usize, counts: &[usize]) -> usize {
+        let open: usize = counts.iter().zip(&self.strides).map(|(c, s)| c * s).sum();
+        index * self.states + open
+    }
+
+    // Best total for players[index..] given the open slots in `counts`.
+    fn best(&mut self, index: usize, counts: &mut [usize]) -> f64 {
+        if index == self.scores.len() {
+            return 0.0;
+        }
+
+        let key = self.key(index, counts);
+        if let Some((score, _)) = self.memo[key] {
+            return score;
+        }
+
+        let mut best_score = f64::MIN;
+        let mut best_slot = usize::MAX;
+
+        for slot in 0..counts.len() {
+            if counts[slot] == 0 {
+                continue;
+            }
+
+            if let Some(score) = self.scores[index][slot] {
+                counts[slot] -= 1;
+                let rest = self.best(index + 1, counts);
+                counts[slot] += 1;
+
+                // Nobody left can be placed after this choice
+                if rest == f64::MIN {
+                    continue;
+                }
+
+                if score + rest > best_score {
+                    best_score = score + rest;
+                    best_slot = slot;
+                }
+            }
+        }
+
+        self.memo[key] = Some((best_score, best_slot));
+        best_score
+    }
+
+    // Walks the memo from the start to recover each player's group.
+    fn choices(&self, counts: &mut [usize]) -> Vec<usize> {
+        let mut choices = Vec::new();
+
+        for index in 0..self.scores.len() {
+            match self.memo[self.key(index, counts)] {
+                Some((_, slot)) if slot != usize::MAX => {
+                    counts[slot] -= 1;
+                    choices.push(slot);
+                }
+                _ => break,
+            }
+        }
+
+        choices
+    }
+}
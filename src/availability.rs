@@ -0,0 +1,170 @@
+// src/availability.rs
+
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::file_handling::open_file;
+use crate::player::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Available,
+    // Out for this many upcoming matches
+    Injured(usize),
+    Suspended(usize),
+    // Chance of playing, between 0 and 1
+    Doubtful(f64),
+}
+
+// What to do with doubtful players when picking a single lineup.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DoubtfulHandling {
+    // Scale their scores by the chance they play
+    #[default]
+    Expected,
+    // Leave them out
+    Exclude,
+    // Treat them as available
+    Play,
+}
+
+impl DoubtfulHandling {
+    pub fn from_name(name: &str) -> Option<DoubtfulHandling> {
+        match name.to_lowercase().as_str() {
+            "expected" => Some(DoubtfulHandling::Expected),
+            "exclude" => Some(DoubtfulHandling::Exclude),
+            "play" => Some(DoubtfulHandling::Play),
+            _ => None,
+        }
+    }
+}
+
+// Player name → status, as read from an availability file. Players not
+// listed are available.
+#[derive(Debug, Clone, Default)]
+pub struct Availability {
+    pub statuses: Vec<(String, Status)>,
+}
+
+impl Availability {
+    pub fn status(&self, name: &str) -> Status {
+        self.statuses.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, status)| *status)
+            .unwrap_or(Status::Available)
+    }
+
+    // How many upcoming matches the player misses.
+    pub fn matches_missed(&self, name: &str) -> usize {
+        match self.status(name) {
+            Status::Injured(matches) | Status::Suspended(matches) => matches,
+            _ => 0,
+        }
+    }
+
+    // Applies `handling` to doubtful players. Injured and suspended players
+    // are kept; see `remove_unavailable`.
+    pub fn apply_doubtful(&self, players: Vec<Player>, handling: DoubtfulHandling) -> Vec<Player> {
+        players.into_iter().filter_map(|mut player| {
+            let Status::Doubtful(chance) = self.status(&player.name) else {
+                return Some(player);
+            };
+            match handling {
+                DoubtfulHandling::Expected => {
+                    player.play_chance = chance;
+                    Some(player)
+                }
+                DoubtfulHandling::Exclude => None,
+                DoubtfulHandling::Play => Some(player),
+            }
+        }).collect()
+    }
+
+    // Players who can play the next match.
+    pub fn remove_unavailable(&self, players: Vec<Player>) -> Vec<Player> {
+        players.into_iter().filter(|p| self.matches_missed(&p.name) == 0).collect()
+    }
+
+    // One line per listed player who isn't simply available, plus a warning
+    // for names that aren't on the roster.
+    pub fn summary(&self, players: &[Player], handling: DoubtfulHandling) -> Vec<String> {
+        self.statuses.iter().filter_map(|(name, status)| {
+            if !players.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+                return Some(format!("Warning: {} is in the availability file but not on the roster", name));
+            }
+            let matches = |n: usize| if n == 1 { "1 match".to_string() } else { format!("{} matches", n) };
+            match *status {
+                Status::Available => None,
+                Status::Injured(n) => Some(format!("{}: injured, out for {}", name, matches(n))),
+                Status::Suspended(n) => Some(format!("{}: suspended for {}", name, matches(n))),
+                Status::Doubtful(chance) => Some(format!("{}: doubtful ({:.0}% to play, {})", name, chance * 100.0, match handling {
+                    DoubtfulHandling::Expected => "scores scaled",
+                    DoubtfulHandling::Exclude => "left out",
+                    DoubtfulHandling::Play => "treated as available",
+                })),
+            }
+        }).collect()
+    }
+}
+
+pub fn read_availability(path: &str) -> Result<Availability> {
+    let mut text = String::new();
+    open_file(path)?.read_to_string(&mut text)?;
+    parse_availability(&text)
+}
+
+// One player per line: `Name: status`, where status is `available`,
+// `injured <matches>`, `suspended [matches]` (default 1) or
+// `doubtful <chance>` (0-1, or a percentage like 60%). `#` starts a comment.
+pub fn parse_availability(text: &str) -> Result<Availability> {
+    let mut statuses = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = |msg: String| Error::new(
+            ErrorKind::InvalidData,
+            format!("Availability line {}: {}", number + 1, msg),
+        );
+
+        let Some((name, status)) = line.rsplit_once(':') else {
+            return Err(invalid(format!("expected 'Name: status', found '{}'", line)));
+        };
+
+        let status = parse_status(status).map_err(invalid)?;
+        statuses.push((name.trim().to_string(), status));
+    }
+
+    Ok(Availability { statuses })
+}
+
+fn parse_status(text: &str) -> std::result::Result<Status, String> {
+    let mut parts = text.split_whitespace();
+    let kind = parts.next().unwrap_or("").to_lowercase();
+    let value = parts.next();
+
+    let matches = |default: Option<usize>| match value {
+        Some(v) => v.parse::<usize>().map_err(|_| format!("invalid number of matches '{}'", v)),
+        None => default.ok_or_else(|| format!("'{}' needs a number of matches", kind)),
+    };
+
+    match kind.as_str() {
+        "available" | "fit" => Ok(Status::Available),
+        "injured" => Ok(Status::Injured(matches(None)?)),
+        "suspended" => Ok(Status::Suspended(matches(Some(1))?)),
+        "doubtful" => {
+            let value = value.ok_or("'doubtful' needs a chance of playing")?;
+            let chance = match value.strip_suffix('%') {
+                Some(percent) => percent.parse::<f64>().map(|p| p / 100.0),
+                None => value.parse::<f64>(),
+            };
+            match chance {
+                Ok(chance) if (0.0..=1.0).contains(&chance) => Ok(Status::Doubtful(chance)),
+                _ => Err(format!("invalid chance of playing '{}' (use 0-1 or a percentage)", value)),
+            }
+        }
+        _ => Err(format!("unknown status '{}' (expected available, injured, suspended or doubtful)", text.trim())),
+    }
+}
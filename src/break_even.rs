@@ -0,0 +1,224 @@
+// src/break_even.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::pick_lineup;
+use crate::pick::{to_pick_data, PickTempData};
+use crate::player::Player;
+
+// Anything needing more than this is reported as out of reach.
+const MAX_IMPROVEMENT: f64 = 100.0;
+const COARSE_STEP: f64 = 10.0;
+const PRECISION: f64 = 0.5;
+
+pub enum Improvement {
+    PositionScore,
+    Stat(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Needed {
+    Points(f64),
+    OutOfReach,
+    // The player has no value for the stat
+    UnknownStat,
+}
+
+impl Needed {
+    // Closest to starting first, out of reach and unknown last
+    fn sort_key(&self) -> (u8, f64) {
+        match self {
+            Needed::Points(points) => (0, *points),
+            Needed::OutOfReach => (1, 0.0),
+            Needed::UnknownStat => (2, 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakEven {
+    pub name: String,
+    pub target: String,
+    pub needed: Needed,
+}
+
+// For every player outside the optimized lineup, finds the smallest
+// improvement that gets them picked. None if no lineup can be picked at all.
+pub fn find_break_even(
+    players: &[Player],
+    reqs: &PositionRequirements,
+    improvement: &Improvement,
+) -> Option<Vec<BreakEven>> {
+    let pick_data: Vec<PickTempData> = players.iter()
+        .map(|p| to_pick_data(p, reqs))
+        .collect();
+
+    let (lineup, _) = pick_lineup(&pick_data, reqs)?;
+
+    let mut results = Vec::new();
+
+    for i in 0..players.len() {
+        if lineup.iter().any(|s| s.index == i) {
+            continue;
+        }
+
+        let result = match improvement {
+            Improvement::PositionScore => position_break_even(&pick_data, i, reqs),
+            Improvement::Stat(stat) => stat_break_even(players, &pick_data, i, stat, reqs),
+        };
+
+        results.push(result);
+    }
+
+    results.sort_by(|a, b| {
+        let (a, b) = (a.needed.sort_key(), b.needed.sort_key());
+        a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+    });
+
+    Some(results)
+}
+
+fn position_break_even(
+    pick_data: &[PickTempData],
+    index: usize,
+    reqs: &PositionRequirements,
+) -> BreakEven {
+    let player = &pick_data[index];
+
+    let mut positions: Vec<&String> = player.position_scores.keys().collect();
+    positions.sort();
+
+    let mut best = BreakEven {
+        name: player.name.clone(),
+        target: "-".to_string(),
+        needed: Needed::OutOfReach,
+    };
+
+    for pos in positions {
+        let needed = min_improvement(|delta| {
+            let mut trial = pick_data.to_vec();
+            if let Some(score) = trial[index].position_scores.get_mut(pos) {
+                *score += delta;
+            }
+            trial[index].max_score += delta;
+            starts(&trial, reqs, index)
+        });
+
+        if let Some(needed) = needed
+            && best.needed.sort_key() > (0, needed)
+        {
+            best.target = pos.clone();
+            best.needed = Needed::Points(needed);
+        }
+    }
+
+    best
+}
+
+fn stat_break_even(
+    players: &[Player],
+    pick_data: &[PickTempData],
+    index: usize,
+    stat: &str,
+    reqs: &PositionRequirements,
+) -> BreakEven {
+    let player = &players[index];
+
+    // Formulas match stat names case-insensitively, so `spd` means `Spd`
+    let key = player.stats.iter()
+        .find(|(key, value)| key.eq_ignore_ascii_case(stat) && value.is_some())
+        .map(|(key, _)| key.clone());
+
+    let needed = if let Some(key) = key {
+        let needed = min_improvement(|delta| {
+            let mut improved = player.clone();
+            if let Some(Some(value)) = improved.stats.get_mut(&key) {
+                *value += delta.ceil();
+            }
+
+            let mut trial = pick_data.to_vec();
+            trial[index] = to_pick_data(&improved, reqs);
+            starts(&trial, reqs, index)
+        });
+        needed.map_or(Needed::OutOfReach, |needed| Needed::Points(needed.ceil()))
+    } else {
+        Needed::UnknownStat
+    };
+
+    BreakEven {
+        name: player.name.clone(),
+        target: stat.to_string(),
+        needed,
+    }
+}
+
+fn starts(pick_data: &[PickTempData], reqs: &PositionRequirements, index: usize) -> bool {
+    match pick_lineup(pick_data, reqs) {
+        Some((lineup, _)) => lineup.iter().any(|s| s.index == index),
+        None => false,
+    }
+}
+
+// The smallest improvement that makes `starts_with` true. The optimizer is a
+// local search, so starting isn't guaranteed to be monotonic in the
+// improvement: scan in coarse steps for the first one that starts, then bisect
+// within that step. The answer is always an improvement that was checked.
+fn min_improvement(starts_with: impl Fn(f64) -> bool) -> Option<f64> {
+    let mut low = 0.0;
+    let mut high = COARSE_STEP;
+    while !starts_with(high) {
+        if high >= MAX_IMPROVEMENT {
+            return None;
+        }
+        low = high;
+        high = (high + COARSE_STEP).min(MAX_IMPROVEMENT);
+    }
+
+    while high - low > PRECISION {
+        let mid = (low + high) / 2.0;
+        if starts_with(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    Some(high)
+}
+
+pub fn print_break_even_report(results: &[BreakEven], improvement: &Improvement) {
+    if results.is_empty() {
+        println!("Every player is already in the starting lineup.");
+        return;
+    }
+
+    let longest_name = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    let padding = 3;
+
+    let target_header = match improvement {
+        Improvement::PositionScore => "Pos",
+        Improvement::Stat(_) => "Stat",
+    };
+
+    println!("Improvement needed to start\n");
+    println!("{:<name_width$}{:<8}Needed", "Name", target_header, name_width = longest_name + padding);
+
+    for result in results {
+        let out_of_reach = format!(">{}", MAX_IMPROVEMENT);
+        match result.needed {
+            Needed::Points(needed) => println!(
+                "{:<name_width$}{:<8}{:>+6.1}",
+                result.name,
+                result.target,
+                needed,
+                name_width = longest_name + padding
+            ),
+            Needed::OutOfReach | Needed::UnknownStat => println!(
+                "{:<name_width$}{:<8}{:>6}",
+                result.name,
+                result.target,
+                if result.needed == Needed::UnknownStat { "unknown stat" } else { &out_of_reach },
+                name_width = longest_name + padding
+            ),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// src/captain.rs
+
+use crate::composition::PositionRequirements;
+use crate::evaluator::evaluate_in;
+use crate::lineup::StartingPosition;
+use crate::player::Player;
+
+// Rates every starter with the composition's captain formula, best first.
+// Empty if the composition has no formula.
+pub fn rank_captains(
+    players: &[Player],
+    lineup: &[StartingPosition],
+    reqs: &PositionRequirements,
+) -> Vec<(String, f64)> {
+    let Some(formula) = &reqs.captain.formula else {
+        return Vec::new();
+    };
+
+    let mut ranked: Vec<(String, f64)> = lineup.iter()
+        .filter_map(|starter| players.iter().find(|p| p.name == starter.name))
+        .filter_map(|player| match evaluate_in(player, formula, reqs) {
+            Ok(score) if score.is_nan() => {
+                eprintln!("Error rating {} as captain: the formula isn't a number", player.name);
+                None
+            }
+            Ok(score) => Some((player.name.clone(), score)),
+            Err(err) => {
+                eprintln!("Error rating {} as captain: {:?}", player.name, err);
+                None
+            }
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+pub fn print_captain_report(players: &[Player], lineup: &[StartingPosition], reqs: &PositionRequirements) {
+    let captain = players.iter().find(|p| p.captain);
+
+    if let Some((name, score)) = rank_captains(players, lineup, reqs).first() {
+        match captain {
+            Some(captain) if captain.name == *name => {
+                println!("Recommended captain: {} ({:.1}), already captain", name, score);
+            }
+            Some(captain) => {
+                println!("Recommended captain: {} ({:.1}), currently {}", name, score, captain.name);
+            }
+            None => println!("Recommended captain: {} ({:.1})", name, score),
+        }
+    }
+}
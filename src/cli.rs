@@ -1,34 +1,166 @@
 // src/cli.rs
+use std::path::Path;
+
+use crate::availability::DoubtfulHandling;
 use crate::file_handling::file_exists;
+use crate::history::DEFAULT_HISTORY_DIR;
+use crate::roster::RosterFormat;
 
 pub struct Config {
     pub team_file: String,
     pub comp_file: String,
+    pub roster_format: Option<RosterFormat>,
+    pub formation: Option<String>,
+    pub availability_file: Option<String>,
+    pub doubtful: DoubtfulHandling,
+    pub history_dir: String,
     pub using_defaults: bool,
+    pub mode: Mode,
+    pub output_format: OutputFormat,
+    pub output_file: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+pub enum Mode {
+    Pick,
+    Train { points: i32 },
+    BreakEven { stat: Option<String> },
+    Transfer { candidates_file: String, price_column: Option<String> },
+    Release,
+    Compare { opponent_file: String },
+    League { dir: String },
+    ExportJson,
+    ConvertComposition,
+    AllFormations,
+    Rotation { matches: usize },
+    Simulate { runs: usize, noise: f64, absence: f64, seed: u64 },
+    Sweep { constant: String, from: f64, to: f64, step: Option<f64> },
+    Fit { labels_file: String },
+    Snapshot,
+    Deltas { from: Option<String> },
+    Progress,
 }
 
 pub enum ArgParseResult {
     Exit,
-    Config(Config),
+    Config(Box<Config>),
 }
 
 static VALID_FLAGS: &[&str] = &[
     "-h", "--help", 
     "-c", "--composition",
-    "-t", "--team-data"
+    "-t", "--team-data",
+    "--roster-format",
+    "-f", "--formation",
+    "-a", "--availability",
+    "--doubtful",
+    "--all-formations",
+    "--rotation",
+    "--simulate",
+    "--noise",
+    "--absence",
+    "--seed",
+    "--sweep",
+    "--fit",
+    "--snapshot",
+    "--deltas",
+    "--progress",
+    "--history-dir",
+    "--train",
+    "--break-even",
+    "--transfer",
+    "--price",
+    "--release",
+    "--compare",
+    "--json",
+    "--csv",
+    "--league",
+    "--export-json",
+    "--convert-composition",
+    "-o", "--output",
+];
+
+// Flags that pick what the program does instead of picking a lineup.
+static MODE_FLAGS: &[&str] = &[
+    "--train",
+    "--rotation",
+    "--simulate",
+    "--sweep",
+    "--fit",
+    "--snapshot",
+    "--deltas",
+    "--progress",
+    "--break-even",
+    "--transfer",
+    "--release",
+    "--compare",
+    "--league",
+    "--export-json",
+    "--convert-composition",
+    "--all-formations",
 ];
 
 pub fn print_help() {
     println!(
-        "Usage: team_picker [-h] [-c <composition_file>] [-t <team_data_file>]
+        "Usage: team_picker [-h] [-c <composition_file>] [-t <team_data_file>] [mode]
 
 Options:
   -c, --composition <file>      Path to composition file
   -t, --team-data <file>        Path to team data file
-  -h, --help                    Show this help text"
+  -f, --formation <name>        Formation to use from the composition file (default: the first)
+  -a, --availability <file>     Injured, suspended and doubtful players (see README)
+  --doubtful <mode>             Doubtful players: expected, exclude or play (default: expected)
+      --history-dir <dir>       Where roster snapshots are kept (default: history)
+      --roster-format <format>  Team data format: paste, csv, tsv, json or html (default: by extension)
+  -o, --output <file>           Write the report to a file instead of the screen
+      --json                    Write the report as JSON (--compare only)
+      --csv                     Write the report as CSV (--league only)
+  -h, --help                    Show this help text
+
+Modes (default: pick the best lineup):
+  --train [points]              Rank stat increases by team total gain (default: 5 points)
+  --break-even [stat]           Show how much each bench player must improve to start
+  --transfer <file>             Rank candidate players from another team data file
+      --price <column>          Net each candidate's gain against this stat column
+  --release                     Show how much the team loses without each player
+  --compare <file>              Compare against an opponent's team data file
+  --league <dir>                Rank every team data file in a directory
+  --export-json                 Convert the team data file to JSON (use -o to save it)
+  --convert-composition         Convert the composition file to JSON (use -o to save it)
+  --all-formations              Optimize every formation and show which scores best
+  --rotation [matches]          Plan lineups for upcoming matches with rest (default: 3 matches)
+  --simulate [runs]             Re-pick with noisy stats and absences (default: 500 runs)
+      --noise <percent>         Standard deviation of each stat (default: 5)
+      --absence <percent>       Chance each player misses a run (default: 0)
+      --seed <n>                Random seed, for repeatable runs (default: 1)
+  --sweep <NAME=from:to[:step]> Re-pick the lineup across a range of a composition constant
+  --fit <file>                  Fit formula coefficients to preferences or target scores (use -o to save)
+  --snapshot                    Save a timestamped copy of the team data file
+  --deltas [snapshot]           Show stat changes from a snapshot (default: the previous) to the latest
+  --progress                    Show each player's best position score and lineup spot per snapshot"
     );
 }
 
+// `NAME=from:to` or `NAME=from:to:step`.
+fn parse_sweep(spec: &str) -> Option<(String, f64, f64, Option<f64>)> {
+    let (name, range) = spec.split_once('=')?;
+    let numbers: Vec<f64> = range.split(':')
+        .map(|s| s.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+        .collect::<Option<_>>()?;
+
+    match numbers.as_slice() {
+        [from, to] => Some((name.trim().to_string(), *from, *to, None)),
+        [from, to, step] if *step > 0.0 => Some((name.trim().to_string(), *from, *to, Some(*step))),
+        _ => None,
+    }
+}
+
 pub fn argument_error(msg: &str) -> ArgParseResult {
     eprintln!("Error: {msg}");
     print_help();
@@ -62,17 +194,223 @@ pub fn from_args() -> ArgParseResult {
         return argument_error(&format!("Team data file not found: {}", team_file));
     }
 
+    if (parser.has_flag("-f") || parser.has_flag("--formation"))
+        && parser.value_of(&["-f", "--formation"]).is_none()
+    {
+        return argument_error("--formation requires a formation name");
+    }
+    let formation = parser.value_of(&["-f", "--formation"]).map(|s| s.to_string());
+
+    if (parser.has_flag("-a") || parser.has_flag("--availability"))
+        && parser.value_of(&["-a", "--availability"]).is_none()
+    {
+        return argument_error("--availability requires a file");
+    }
+    let availability_file = parser.value_of(&["-a", "--availability"]).map(|s| s.to_string());
+    if let Some(file) = &availability_file
+        && file_exists(file).is_err()
+    {
+        return argument_error(&format!("Availability file not found: {}", file));
+    }
+
+    let doubtful = match parser.value_of(&["--doubtful"]) {
+        Some(name) => match DoubtfulHandling::from_name(name) {
+            Some(handling) => handling,
+            None => return argument_error(&format!("Unknown doubtful handling: {name}")),
+        },
+        None if parser.has_flag("--doubtful") => {
+            return argument_error("--doubtful requires expected, exclude or play");
+        }
+        None => DoubtfulHandling::default(),
+    };
+
+    if parser.has_flag("--history-dir") && parser.value_of(&["--history-dir"]).is_none() {
+        return argument_error("--history-dir requires a directory");
+    }
+    let history_dir = parser.value_of(&["--history-dir"]).unwrap_or(DEFAULT_HISTORY_DIR).to_string();
+
+    let roster_format = match parser.value_of(&["--roster-format"]) {
+        Some(name) => match RosterFormat::from_name(name) {
+            Some(format) => Some(format),
+            None => return argument_error(&format!("Unknown roster format: {name}")),
+        },
+        None => None,
+    };
+
+    // Each mode is its own report, so only one of them can run
+    let modes: Vec<&str> = MODE_FLAGS.iter().copied().filter(|flag| parser.has_flag(flag)).collect();
+    if modes.len() > 1 {
+        return argument_error(&format!("Only one mode at a time, got {}", modes.join(" and ")));
+    }
+
+    // Options of a single mode; don't drop them silently either
+    for (option, mode) in [
+        ("--noise", "--simulate"),
+        ("--absence", "--simulate"),
+        ("--seed", "--simulate"),
+        ("--price", "--transfer"),
+    ] {
+        if parser.has_flag(option) && !parser.has_flag(mode) {
+            return argument_error(&format!("{option} is only supported with {mode}"));
+        }
+    }
+
+    let mode = if parser.has_flag("--train") {
+        let points = match parser.value_of(&["--train"]) {
+            Some(value) => match value.parse::<i32>() {
+                Ok(points) => points,
+                Err(_) => return argument_error(&format!("Invalid number of training points: {value}")),
+            },
+            None => 5,
+        };
+        Mode::Train { points }
+    } else if parser.has_flag("--rotation") {
+        let matches = match parser.value_of(&["--rotation"]) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(matches) if matches > 0 => matches,
+                _ => return argument_error(&format!("Invalid number of matches: {value}")),
+            },
+            None => 3,
+        };
+        Mode::Rotation { matches }
+    } else if parser.has_flag("--simulate") {
+        let runs = match parser.value_of(&["--simulate"]) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(runs) if runs > 0 => runs,
+                _ => return argument_error(&format!("Invalid number of runs: {value}")),
+            },
+            None => 500,
+        };
+        let percent = |flag: &str, default: f64| match parser.value_of(&[flag]) {
+            Some(value) => value.parse::<f64>()
+                .ok()
+                .filter(|v| (0.0..=100.0).contains(v))
+                .map(|v| v / 100.0)
+                .ok_or(format!("Invalid percentage for {flag}: {value}")),
+            None => Ok(default),
+        };
+        let noise = match percent("--noise", 0.05) {
+            Ok(noise) => noise,
+            Err(msg) => return argument_error(&msg),
+        };
+        let absence = match percent("--absence", 0.0) {
+            Ok(absence) => absence,
+            Err(msg) => return argument_error(&msg),
+        };
+        let seed = match parser.value_of(&["--seed"]) {
+            Some(value) => match value.parse::<u64>() {
+                Ok(seed) => seed,
+                Err(_) => return argument_error(&format!("Invalid seed: {value}")),
+            },
+            None => 1,
+        };
+        Mode::Simulate { runs, noise, absence, seed }
+    } else if parser.has_flag("--sweep") {
+        let Some(spec) = parser.value_of(&["--sweep"]) else {
+            return argument_error("--sweep requires NAME=from:to[:step]");
+        };
+        match parse_sweep(spec) {
+            Some((constant, from, to, step)) => Mode::Sweep { constant, from, to, step },
+            None => return argument_error(&format!("Invalid sweep '{spec}', expected NAME=from:to[:step]")),
+        }
+    } else if parser.has_flag("--fit") {
+        let Some(labels_file) = parser.value_of(&["--fit"]) else {
+            return argument_error("--fit requires a label file");
+        };
+        if file_exists(labels_file).is_err() {
+            return argument_error(&format!("Label file not found: {}", labels_file));
+        }
+        Mode::Fit { labels_file: labels_file.to_string() }
+    } else if parser.has_flag("--snapshot") {
+        Mode::Snapshot
+    } else if parser.has_flag("--deltas") {
+        let from = parser.value_of(&["--deltas"]).map(|s| s.to_string());
+        Mode::Deltas { from }
+    } else if parser.has_flag("--progress") {
+        Mode::Progress
+    } else if parser.has_flag("--break-even") {
+        let stat = parser.value_of(&["--break-even"]).map(|s| s.to_string());
+        Mode::BreakEven { stat }
+    } else if parser.has_flag("--transfer") {
+        let Some(candidates_file) = parser.value_of(&["--transfer"]) else {
+            return argument_error("--transfer requires a candidate file");
+        };
+        if file_exists(candidates_file).is_err() {
+            return argument_error(&format!("Candidate file not found: {}", candidates_file));
+        }
+        let price_column = parser.value_of(&["--price"]).map(|s| s.to_string());
+        Mode::Transfer { candidates_file: candidates_file.to_string(), price_column }
+    } else if parser.has_flag("--release") {
+        Mode::Release
+    } else if parser.has_flag("--compare") {
+        let Some(opponent_file) = parser.value_of(&["--compare"]) else {
+            return argument_error("--compare requires an opponent team data file");
+        };
+        if file_exists(opponent_file).is_err() {
+            return argument_error(&format!("Opponent file not found: {}", opponent_file));
+        }
+        Mode::Compare { opponent_file: opponent_file.to_string() }
+    } else if parser.has_flag("--league") {
+        let Some(dir) = parser.value_of(&["--league"]) else {
+            return argument_error("--league requires a directory");
+        };
+        if !Path::new(dir).is_dir() {
+            return argument_error(&format!("League directory not found: {}", dir));
+        }
+        Mode::League { dir: dir.to_string() }
+    } else if parser.has_flag("--export-json") {
+        Mode::ExportJson
+    } else if parser.has_flag("--convert-composition") {
+        Mode::ConvertComposition
+    } else if parser.has_flag("--all-formations") {
+        Mode::AllFormations
+    } else {
+        Mode::Pick
+    };
+
+    let output_format = if parser.has_flag("--json") {
+        OutputFormat::Json
+    } else if parser.has_flag("--csv") {
+        OutputFormat::Csv
+    } else {
+        OutputFormat::Text
+    };
+
+    // Only some reports have other formats; don't drop the flag silently
+    match (output_format, &mode) {
+        (OutputFormat::Text, _) | (OutputFormat::Json, Mode::Compare { .. }) | (OutputFormat::Csv, Mode::League { .. }) => {}
+        (OutputFormat::Json, _) => return argument_error("--json is only supported with --compare"),
+        (OutputFormat::Csv, _) => return argument_error("--csv is only supported with --league"),
+    }
+
+    if (parser.has_flag("-o") || parser.has_flag("--output"))
+        && parser.value_of(&["-o", "--output"]).is_none()
+    {
+        return argument_error("--output requires a file name");
+    }
+    let output_file = parser.value_of(&["-o", "--output"]).map(|s| s.to_string());
+
+    // League mode reads its own directory of teams, so there's no team file to create.
     let using_defaults = 
+        !matches!(mode, Mode::League { .. }) &&
         !team_user_specified && 
         !comp_user_specified &&
         team_file == "team_data.txt" &&
         comp_file == "composition.txt";
 
-    ArgParseResult::Config(Config {
+    ArgParseResult::Config(Box::new(Config {
         team_file: team_file.to_string(),
         comp_file: comp_file.to_string(),
-        using_defaults
-    })
+        roster_format,
+        formation,
+        availability_file,
+        doubtful,
+        history_dir,
+        using_defaults,
+        mode,
+        output_format,
+        output_file,
+    }))
 }
 
 pub struct ArgParser { 
@@ -106,7 +444,7 @@ impl ArgParser {
 
     pub fn check_unrecognized(&self) -> Option<String> {
         for arg in &self.args {
-            if arg.starts_with('-') && !self.valid_flags.contains(&&arg.as_str()) {
+            if arg.starts_with('-') && !self.valid_flags.contains(&arg.as_str()) {
                 return Some(arg.clone());
             }
         }
@@ -0,0 +1,209 @@
+// src/compare.rs
+
+use crate::composition::PositionRequirements;
+use crate::json::JsonValue;
+use crate::lineup::{PositionDescription, StartingPosition};
+
+// Picks the offensive or defensive half of a starting position.
+type UnitSide = fn(&StartingPosition) -> &PositionDescription;
+
+#[derive(Debug, Clone)]
+pub struct SlotMatchup {
+    pub unit: String,
+    pub position: String,
+    pub ours: Option<(String, f64)>,
+    pub theirs: Option<(String, f64)>,
+}
+
+impl SlotMatchup {
+    pub fn advantage(&self) -> f64 {
+        let ours = self.ours.as_ref().map(|(_, s)| *s).unwrap_or(0.0);
+        let theirs = self.theirs.as_ref().map(|(_, s)| *s).unwrap_or(0.0);
+        ours - theirs
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamComparison {
+    pub our_name: String,
+    pub their_name: String,
+    pub slots: Vec<SlotMatchup>,
+}
+
+impl TeamComparison {
+    fn unit_totals(&self, unit: &str) -> (f64, f64) {
+        self.slots.iter()
+            .filter(|s| s.unit == unit)
+            .fold((0.0, 0.0), |(ours, theirs), s| {
+                (
+                    ours + s.ours.as_ref().map(|(_, v)| *v).unwrap_or(0.0),
+                    theirs + s.theirs.as_ref().map(|(_, v)| *v).unwrap_or(0.0),
+                )
+            })
+    }
+
+    // Summed advantage per position, in composition order.
+    pub fn position_advantages(&self) -> Vec<(String, f64)> {
+        let mut advantages: Vec<(String, f64)> = Vec::new();
+        for slot in &self.slots {
+            match advantages.iter_mut().find(|(pos, _)| *pos == slot.position) {
+                Some((_, total)) => *total += slot.advantage(),
+                None => advantages.push((slot.position.clone(), slot.advantage())),
+            }
+        }
+        advantages
+    }
+}
+
+// Lines both optimized lineups up slot by slot. Within a position the
+// strongest player on each side is matched against each other.
+pub fn compare_lineups(
+    our_name: &str,
+    ours: &[StartingPosition],
+    their_name: &str,
+    theirs: &[StartingPosition],
+    reqs: &PositionRequirements,
+) -> TeamComparison {
+    let mut slots = Vec::new();
+
+    let units: [(&str, &[String], UnitSide); 2] = [
+        ("Offense", &reqs.attacking, |s| &s.offense),
+        ("Defense", &reqs.defensive, |s| &s.defense),
+    ];
+
+    for (unit, positions, side) in units {
+        let mut seen: Vec<&String> = Vec::new();
+
+        for pos in positions {
+            if seen.contains(&pos) {
+                continue;
+            }
+            seen.push(pos);
+
+            let our_players = players_at(ours, pos, side);
+            let their_players = players_at(theirs, pos, side);
+            let count = positions.iter().filter(|p| *p == pos).count();
+
+            for i in 0..count {
+                slots.push(SlotMatchup {
+                    unit: unit.to_string(),
+                    position: pos.clone(),
+                    ours: our_players.get(i).cloned(),
+                    theirs: their_players.get(i).cloned(),
+                });
+            }
+        }
+    }
+
+    TeamComparison {
+        our_name: our_name.to_string(),
+        their_name: their_name.to_string(),
+        slots,
+    }
+}
+
+fn players_at(
+    lineup: &[StartingPosition],
+    pos: &str,
+    side: UnitSide,
+) -> Vec<(String, f64)> {
+    let mut players: Vec<(String, f64)> = lineup.iter()
+        .filter(|s| side(s).position == pos)
+        .map(|s| (s.name.clone(), side(s).score))
+        .collect();
+    players.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    players
+}
+
+pub fn format_comparison_text(comparison: &TeamComparison) -> String {
+    let mut out = String::new();
+
+    let name_width = comparison.slots.iter()
+        .flat_map(|s| [&s.ours, &s.theirs])
+        .filter_map(|p| p.as_ref().map(|(name, _)| name.len()))
+        .chain([comparison.our_name.len(), comparison.their_name.len()])
+        .max()
+        .unwrap_or(0) + 3;
+
+    out.push_str(&format!("Head-to-head: {} vs {}\n", comparison.our_name, comparison.their_name));
+
+    for unit in ["Offense", "Defense"] {
+        out.push_str(&format!(
+            "\n{}\n{:<6}{:<name_width$}{:>5}   {:<name_width$}{:>5}{:>7}\n",
+            unit, "Pos", comparison.our_name, "", comparison.their_name, "", "Diff"
+        ));
+
+        for slot in comparison.slots.iter().filter(|s| s.unit == unit) {
+            let (our_player, our_score) = split_player(&slot.ours);
+            let (their_player, their_score) = split_player(&slot.theirs);
+            out.push_str(&format!(
+                "{:<6}{:<name_width$}{:>5}   {:<name_width$}{:>5}{:>+7.0}\n",
+                slot.position, our_player, our_score, their_player, their_score, slot.advantage()
+            ));
+        }
+    }
+
+    out.push_str("\nUnit totals\n");
+    let (our_off, their_off) = comparison.unit_totals("Offense");
+    let (our_def, their_def) = comparison.unit_totals("Defense");
+    for (unit, ours, theirs) in [
+        ("Offense", our_off, their_off),
+        ("Defense", our_def, their_def),
+        ("Total", our_off + our_def, their_off + their_def),
+    ] {
+        out.push_str(&format!("    {:<10}{:>6.0}{:>6.0}{:>+7.0}\n", unit, ours, theirs, ours - theirs));
+    }
+
+    out.push_str("\nAdvantage by position\n");
+    for (pos, advantage) in comparison.position_advantages() {
+        out.push_str(&format!("    {:<10}{:>+7.0}\n", pos, advantage));
+    }
+
+    out
+}
+
+fn split_player(player: &Option<(String, f64)>) -> (String, String) {
+    match player {
+        Some((name, score)) => (name.clone(), format!("{:.0}", score)),
+        None => ("-".to_string(), "-".to_string()),
+    }
+}
+
+pub fn comparison_to_json(comparison: &TeamComparison) -> JsonValue {
+    let player_json = |player: &Option<(String, f64)>| match player {
+        Some((name, score)) => JsonValue::object(vec![
+            ("name", JsonValue::string(name)),
+            ("score", JsonValue::Number(*score)),
+        ]),
+        None => JsonValue::Null,
+    };
+
+    let slots = comparison.slots.iter().map(|slot| JsonValue::object(vec![
+        ("unit", JsonValue::string(&slot.unit)),
+        ("position", JsonValue::string(&slot.position)),
+        ("ours", player_json(&slot.ours)),
+        ("theirs", player_json(&slot.theirs)),
+        ("advantage", JsonValue::Number(slot.advantage())),
+    ])).collect();
+
+    let (our_off, their_off) = comparison.unit_totals("Offense");
+    let (our_def, their_def) = comparison.unit_totals("Defense");
+    let totals = |off: f64, def: f64| JsonValue::object(vec![
+        ("offense", JsonValue::Number(off)),
+        ("defense", JsonValue::Number(def)),
+        ("total", JsonValue::Number(off + def)),
+    ]);
+
+    let advantages = comparison.position_advantages().into_iter()
+        .map(|(pos, advantage)| (pos, JsonValue::Number(advantage)))
+        .collect();
+
+    JsonValue::object(vec![
+        ("ours", JsonValue::string(&comparison.our_name)),
+        ("theirs", JsonValue::string(&comparison.their_name)),
+        ("slots", JsonValue::Array(slots)),
+        ("our_totals", totals(our_off, our_def)),
+        ("their_totals", totals(their_off, their_def)),
+        ("position_advantages", JsonValue::Object(advantages)),
+    ])
+}
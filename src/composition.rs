@@ -1,33 +1,425 @@
 // src/composition.rs
 
 use std::collections::HashMap;
-use std::io::{BufRead, Result};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
 use crate::file_handling::open_file;
+use crate::json::JsonValue;
+use crate::objective::Objective;
+use crate::player::Player;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PositionRequirements {
     pub attacking: Vec<String>,
     pub defensive: Vec<String>,
+    // Multiplier for each slot in `attacking`/`defensive` (missing means 1)
+    pub attacking_weights: Vec<f64>,
+    pub defensive_weights: Vec<f64>,
     pub position_to_calculation: HashMap<String, String>,
+    // Free-form details such as name, description and author
+    pub metadata: Vec<(String, String)>,
+    // Multiplier for a position's score in the team total (default 1)
+    pub position_weights: HashMap<String, f64>,
+    // Only players for whom this expression is true may play the position
+    pub eligibility: HashMap<String, String>,
+    // How slot scores add up to the lineup score (default: the sum)
+    pub objective: Option<Objective>,
+    pub offense_unit: UnitSettings,
+    pub defense_unit: UnitSettings,
+    pub captain: CaptainSettings,
+    // How many matches in a row a player can start, for rotation planning
+    pub stamina: Option<String>,
+    // Named numbers formulas can use (`const SPEED_CUT = 50`)
+    pub constants: HashMap<String, f64>,
 }
 
-pub fn parse_composition(path: &str) -> Result<PositionRequirements> {
-    let reader = open_file(path)?;
+// Whether the roster's captain must start, and how to rate captain candidates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptainSettings {
+    pub required: Option<bool>,
+    pub formula: Option<String>,
+}
+
+impl CaptainSettings {
+    pub fn required(&self) -> bool {
+        self.required.unwrap_or(false)
+    }
+}
+
+// Weight and minimum raw total for the offensive or defensive unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UnitSettings {
+    pub weight: Option<f64>,
+    pub minimum: Option<f64>,
+}
+
+impl UnitSettings {
+    pub fn weight(&self) -> f64 {
+        self.weight.unwrap_or(1.0)
+    }
+
+    fn overlay(&mut self, layer: &UnitSettings) {
+        if layer.weight.is_some() {
+            self.weight = layer.weight;
+        }
+        if layer.minimum.is_some() {
+            self.minimum = layer.minimum;
+        }
+    }
+}
+
+impl PositionRequirements {
+    pub fn weight(&self, position: &str) -> f64 {
+        self.position_weights.get(position).copied().unwrap_or(1.0)
+    }
+
+    pub fn objective(&self) -> Objective {
+        self.objective.unwrap_or_default()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.metadata.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("name"))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// A named set of slots and formulas. Files without `Formation:` sections
+// hold a single formation called "Default".
+pub type Formation = (String, PositionRequirements);
+
+// Reads a composition, as JSON if the file ends in .json and as the
+// `Offense:`/`Defense:`/`KEY=expr` text format otherwise.
+pub fn parse_formations(path: &str) -> Result<Vec<Formation>> {
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        let mut text = String::new();
+        open_file(path)?.read_to_string(&mut text)?;
+        parse_formations_json(&text)
+    } else {
+        let formations = read_text_composition(Path::new(path), &mut Vec::new())?.into_formations();
+        check_slot_counts(&formations)?;
+        Ok(formations)
+    }
+}
+
+// Constants named like a roster stat (case-insensitive). Formulas read the
+// constant first, so it hides that stat for every player.
+pub fn shadowed_stats(formations: &[Formation], players: &[Player]) -> Vec<String> {
+    let mut shadowed: Vec<String> = formations.iter()
+        .flat_map(|(_, reqs)| reqs.constants.keys())
+        .filter(|name| players.iter().any(|p| p.stats.keys().any(|stat| stat.eq_ignore_ascii_case(name))))
+        .cloned()
+        .collect();
+    shadowed.sort();
+    shadowed.dedup();
+    shadowed
+}
+
+// Every player takes one offense and one defense slot, so each formation,
+// with its overrides and includes applied, needs as many of one as the other.
+fn check_slot_counts(formations: &[Formation]) -> Result<()> {
+    for (name, reqs) in formations {
+        if reqs.attacking.len() != reqs.defensive.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Formation '{}' has {} offense slots but {} defense slots",
+                    name,
+                    reqs.attacking.len(),
+                    reqs.defensive.len()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Picks a formation by name (case-insensitive), or the first one.
+pub fn select_formation(formations: Vec<Formation>, name: Option<&str>) -> Result<PositionRequirements> {
+    let names: Vec<String> = formations.iter().map(|(n, _)| n.clone()).collect();
+
+    let found = match name {
+        Some(name) => formations.into_iter().find(|(n, _)| n.eq_ignore_ascii_case(name)),
+        None => formations.into_iter().next(),
+    };
+
+    found.map(|(_, reqs)| reqs).ok_or_else(|| Error::new(
+        ErrorKind::NotFound,
+        format!("Formation '{}' not found. Available: {}", name.unwrap_or(""), names.join(", ")),
+    ))
+}
+
+// What a text file defines: its shared section and its formations.
+struct TextComposition {
+    common: PositionRequirements,
+    formations: Vec<Formation>,
+}
+
+impl TextComposition {
+    fn into_formations(self) -> Vec<Formation> {
+        if self.formations.is_empty() {
+            vec![("Default".to_string(), self.common)]
+        } else {
+            self.formations
+        }
+    }
+}
+
+// The shared section or one formation, as written. Included definitions and
+// the section's own lines are kept apart so its own lines always win,
+// wherever the `include` sits.
+struct TextSection {
+    name: Option<String>,
+    included: Vec<PositionRequirements>,
+    own: PositionRequirements,
+}
+
+impl TextSection {
+    fn new(name: Option<String>) -> Self {
+        Self { name, included: Vec::new(), own: PositionRequirements::default() }
+    }
 
-    let mut requirements = PositionRequirements {
-        attacking: Vec::new(),
-        defensive: Vec::new(),
-        position_to_calculation: HashMap::new(),
+    // Inherited definitions, then includes in order, then the section's own.
+    fn resolve(&self, inherited: &PositionRequirements) -> PositionRequirements {
+        let mut reqs = inherited.clone();
+        for layer in self.included.iter().chain(std::iter::once(&self.own)) {
+            overlay(&mut reqs, layer);
+        }
+        reqs
+    }
+}
+
+// Slot lists are replaced as a whole; formulas, weights and constraints are
+// replaced per position.
+fn overlay(reqs: &mut PositionRequirements, layer: &PositionRequirements) {
+    if !layer.attacking.is_empty() {
+        reqs.attacking = layer.attacking.clone();
+        reqs.attacking_weights = layer.attacking_weights.clone();
+    }
+    if !layer.defensive.is_empty() {
+        reqs.defensive = layer.defensive.clone();
+        reqs.defensive_weights = layer.defensive_weights.clone();
+    }
+
+    reqs.position_to_calculation.extend(layer.position_to_calculation.clone());
+    reqs.position_weights.extend(layer.position_weights.clone());
+    reqs.eligibility.extend(layer.eligibility.clone());
+    if layer.objective.is_some() {
+        reqs.objective = layer.objective;
+    }
+    reqs.offense_unit.overlay(&layer.offense_unit);
+    reqs.defense_unit.overlay(&layer.defense_unit);
+    if layer.captain.required.is_some() {
+        reqs.captain.required = layer.captain.required;
+    }
+    if layer.captain.formula.is_some() {
+        reqs.captain.formula = layer.captain.formula.clone();
+    }
+    if layer.stamina.is_some() {
+        reqs.stamina = layer.stamina.clone();
+    }
+    reqs.constants.extend(layer.constants.clone());
+
+    for (key, value) in &layer.metadata {
+        match reqs.metadata.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some(existing) => existing.1 = value.clone(),
+            None => reqs.metadata.push((key.clone(), value.clone())),
+        }
+    }
+}
+
+// Reads a text composition file. `chain` holds the files currently being
+// included so a file that (indirectly) includes itself is reported.
+fn read_text_composition(path: &Path, chain: &mut Vec<PathBuf>) -> Result<TextComposition> {
+    let canonical = path.canonicalize().map_err(|_| Error::new(
+        ErrorKind::NotFound,
+        format!("File not found: {}", path.display()),
+    ))?;
+
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain.iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Include cycle: {}", cycle.join(" -> ")),
+        ));
+    }
+
+    let mut text = String::new();
+    open_file(&path.to_string_lossy())?.read_to_string(&mut text)?;
+
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    chain.push(canonical);
+    let result = parse_text(&text, &mut |target| {
+        read_text_composition(&dir.join(target), chain).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::new(
+                ErrorKind::NotFound,
+                format!("{} (included from {})", e, path.display()),
+            ),
+            _ => e,
+        })
+    });
+    chain.pop();
+
+    result
+}
+
+// `const NAME = 50`. None if the line isn't a constant definition.
+fn constant_definition(line: &str) -> Option<std::result::Result<(String, f64), String>> {
+    let (keyword, rest) = line.split_once(char::is_whitespace)?;
+    if !keyword.eq_ignore_ascii_case("const") {
+        return None;
+    }
+
+    let Some((name, value)) = rest.split_once('=') else {
+        return Some(Err("Expected 'const NAME = value'".to_string()));
     };
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Some(Err(format!("Invalid constant name '{}'", name)));
+    }
+
+    Some(match value.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok((name.to_string(), value)),
+        _ => Err(format!("Constant {} must be a number, not '{}'", name, value.trim())),
+    })
+}
+
+// `POS requires <expr>`. Returns the position and the expression.
+fn requirement_rule(line: &str) -> Option<(&str, &str)> {
+    let (position, rest) = line.split_once(char::is_whitespace)?;
+    let (keyword, rule) = rest.trim_start().split_once(char::is_whitespace)?;
+    if !keyword.eq_ignore_ascii_case("requires") || rule.trim().is_empty() {
+        return None;
+    }
+    Some((position, rule.trim()))
+}
+
+// `include "common.txt"` (quotes optional). Returns the file name.
+fn include_target(line: &str) -> Option<&str> {
+    let (keyword, rest) = line.split_once(char::is_whitespace)?;
+    if !keyword.eq_ignore_ascii_case("include") || line.contains('=') {
+        return None;
+    }
+    Some(rest.trim().trim_matches('"'))
+}
+
+// Everything before the first `Formation: <name>` line is shared. Each
+// formation starts from the shared slots and formulas; its own Offense/Defense
+// lines replace the shared slots and its own formulas override shared ones.
+// An included file's shared definitions are merged into the section holding
+// the `include`; its formations are added unless one of the same name exists.
+fn parse_text(
+    text: &str,
+    include: &mut dyn FnMut(&str) -> Result<TextComposition>,
+) -> Result<TextComposition> {
+    let mut sections = vec![TextSection::new(None)];
+    let mut included_formations: Vec<Formation> = Vec::new();
 
-    for line in reader.lines() {
-        let line = line?;
-        let line = trim_comment(&line);
+    for line in text.lines() {
+        let line = trim_comment(line);
 
         if line.is_empty() || line.starts_with('#') {
             continue;
         }
 
+        if let Some((prefix, name)) = line.split_once(':')
+            && prefix.trim().eq_ignore_ascii_case("formation")
+        {
+            sections.push(TextSection::new(Some(name.trim().to_string())));
+            continue;
+        }
+
+        let section = sections.last_mut().unwrap();
+
+        if let Some(target) = include_target(line) {
+            let included = include(target)?;
+            section.included.push(included.common);
+            included_formations.extend(included.formations);
+            continue;
+        }
+
+        let requirements = &mut section.own;
+
+        if let Some(constant) = constant_definition(line) {
+            let (name, value) = constant.map_err(|e| Error::new(ErrorKind::InvalidData, format!("{} in '{}'", e, line)))?;
+            requirements.constants.insert(name, value);
+            continue;
+        }
+
+        // `DL requires Dur >= 50`; several rules for a position must all hold
+        if let Some((position, rule)) = requirement_rule(line) {
+            requirements.eligibility.entry(position.to_string())
+                .and_modify(|existing| *existing = format!("({}) && ({})", existing, rule))
+                .or_insert_with(|| rule.to_string());
+            continue;
+        }
+
+        // `Keyword: value` lines. Formulas may contain ':' but only after '='
+        if let Some((prefix, rest)) = line.split_once(':')
+            && !prefix.contains('=')
+        {
+            let invalid = |e: String| Error::new(ErrorKind::InvalidData, format!("{} in '{}'", e, line));
+
+            let prefix = prefix.trim().to_lowercase();
+            let words: Vec<&str> = prefix.split_whitespace().collect();
+
+            match words.as_slice() {
+                [unit @ ("offense" | "defense")] => {
+                    let (slots, weights) = match *unit {
+                        "offense" => (&mut requirements.attacking, &mut requirements.attacking_weights),
+                        _ => (&mut requirements.defensive, &mut requirements.defensive_weights),
+                    };
+                    for token in rest.split_whitespace() {
+                        let (position, weight) = parse_slot(token).map_err(invalid)?;
+                        slots.push(position);
+                        weights.push(weight);
+                    }
+                }
+                [unit @ ("offense" | "defense"), setting @ ("weight" | "minimum")] => {
+                    let unit = match *unit {
+                        "offense" => &mut requirements.offense_unit,
+                        _ => &mut requirements.defense_unit,
+                    };
+                    let value = parse_unit_value(rest).map_err(invalid)?;
+                    match *setting {
+                        "weight" => unit.weight = Some(value),
+                        _ => unit.minimum = Some(value),
+                    }
+                }
+                ["objective"] => {
+                    requirements.objective = Some(Objective::parse(rest).map_err(invalid)?);
+                }
+                ["weights"] => {
+                    for token in rest.split_whitespace() {
+                        let (position, weight) = parse_slot(token).map_err(invalid)?;
+                        requirements.position_weights.insert(position, weight);
+                    }
+                }
+                ["captain"] => {
+                    requirements.captain.required = Some(match rest.trim().to_lowercase().as_str() {
+                        "required" => true,
+                        "optional" => false,
+                        other => return Err(invalid(format!("Unknown captain setting '{}' (expected required or optional)", other))),
+                    });
+                }
+                ["captain", "formula"] => {
+                    requirements.captain.formula = Some(rest.trim().to_string());
+                }
+                ["stamina", "formula"] => {
+                    requirements.stamina = Some(rest.trim().to_string());
+                }
+                _ => {}
+            }
+            continue;
+        }
+
         if let Some((lhs, rhs)) = line.split_once('=') {
 
             // println!("Before: key='{}', value='{}'", lhs, rhs);
@@ -38,20 +430,316 @@ pub fn parse_composition(path: &str) -> Result<PositionRequirements> {
             // println!("After: key='{}', value='{}'", key, value);
 
             requirements.position_to_calculation.insert(key, value);
-            continue;
         }
+    }
+
+    let mut sections = sections.into_iter();
+    let common = sections.next().unwrap().resolve(&PositionRequirements::default());
+
+    let mut formations: Vec<Formation> = sections
+        .map(|section| (section.name.clone().unwrap_or_default(), section.resolve(&common)))
+        .collect();
+
+    for (name, reqs) in included_formations {
+        if !formations.iter().any(|(n, _)| n.eq_ignore_ascii_case(&name)) {
+            formations.push((name, reqs));
+        }
+    }
+
+    Ok(TextComposition { common, formations })
+}
+
+// {
+//   "metadata": { "name": "...", ... },
+//   "offense": ["RN", ...], "defense": ["DL", ...],
+//   "formulas": { "RN": "max(HB,QB)" },
+//   "weights": { "RN": 1.5 },
+//   "constraints": { "DL": "Dur >= 50" },
+//   "formations": { "Heavy": { "defense": [...], "formulas": {...} } }
+// }
+// Top-level fields are shared; each entry in "formations" overrides them.
+pub fn parse_formations_json(text: &str) -> Result<Vec<Formation>> {
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidData, msg);
+
+    let root = JsonValue::parse(text).map_err(invalid)?;
+    if !matches!(root, JsonValue::Object(_)) {
+        return Err(invalid("Composition JSON must be an object".to_string()));
+    }
+
+    let mut common = PositionRequirements::default();
+    apply_json(&mut common, &root).map_err(invalid)?;
+
+    for (key, value) in object_fields(&root, "metadata").map_err(invalid)? {
+        let value = match value {
+            JsonValue::String(s) => s.clone(),
+            other => other.to_pretty_string(),
+        };
+        common.metadata.push((key.clone(), value));
+    }
+
+    let mut formations = Vec::new();
+    for (name, spec) in object_fields(&root, "formations").map_err(invalid)? {
+        if !matches!(spec, JsonValue::Object(_)) {
+            return Err(invalid(format!("Formation '{}' must be an object", name)));
+        }
+        let mut reqs = common.clone();
+        apply_json(&mut reqs, spec).map_err(|e| invalid(format!("Formation '{}': {}", name, e)))?;
+        formations.push((name.clone(), reqs));
+    }
+
+    if formations.is_empty() {
+        formations.push(("Default".to_string(), common));
+    }
+
+    check_slot_counts(&formations)?;
+    Ok(formations)
+}
+
+// Copies whatever slots, formulas, weights and constraints `spec` defines.
+fn apply_json(requirements: &mut PositionRequirements, spec: &JsonValue) -> std::result::Result<(), String> {
+    for (key, target, weights) in [
+        ("offense", &mut requirements.attacking, &mut requirements.attacking_weights),
+        ("defense", &mut requirements.defensive, &mut requirements.defensive_weights),
+    ] {
+        match spec.get(key) {
+            Some(JsonValue::Array(slots)) => {
+                target.clear();
+                weights.clear();
+                for slot in slots {
+                    let slot = slot.as_str()
+                        .ok_or_else(|| format!("'{}' slots must be strings", key))?;
+                    let (position, weight) = parse_slot(slot)?;
+                    target.push(position);
+                    weights.push(weight);
+                }
+            }
+            Some(_) => return Err(format!("'{}' must be an array of positions", key)),
+            None => {}
+        }
+    }
 
-        if let Some((prefix, rest)) = line.split_once(':') {
-            let target = match prefix.trim().to_lowercase().as_str() {
-                "offense" => &mut requirements.attacking,
-                "defense" => &mut requirements.defensive,
-                _ => continue,
+    for (key, target) in [
+        ("formulas", &mut requirements.position_to_calculation),
+        ("constraints", &mut requirements.eligibility),
+    ] {
+        for (position, value) in object_fields(spec, key)? {
+            let expr = value.as_str()
+                .ok_or_else(|| format!("'{}.{}' must be a string", key, position))?;
+            target.insert(position.clone(), expr.to_string());
+        }
+    }
+
+    for (position, value) in object_fields(spec, "weights")? {
+        let Some(weight) = non_negative(value) else {
+            return Err(format!("'weights.{}' must be a non-negative number", position));
+        };
+        requirements.position_weights.insert(position.clone(), weight);
+    }
+
+    for (unit, settings) in object_fields(spec, "units")? {
+        let target = match unit.to_lowercase().as_str() {
+            "offense" => &mut requirements.offense_unit,
+            "defense" => &mut requirements.defense_unit,
+            _ => return Err(format!("Unknown unit '{}' (expected offense or defense)", unit)),
+        };
+        let JsonValue::Object(fields) = settings else {
+            return Err(format!("'units.{}' must be an object", unit));
+        };
+        for (setting, value) in fields {
+            let Some(value) = non_negative(value) else {
+                return Err(format!("'units.{}.{}' must be a non-negative number", unit, setting));
             };
-            target.extend(rest.split_whitespace().map(|s| s.to_string()));
+            match setting.as_str() {
+                "weight" => target.weight = Some(value),
+                "minimum" => target.minimum = Some(value),
+                _ => return Err(format!("Unknown unit setting '{}' (expected weight or minimum)", setting)),
+            }
+        }
+    }
+
+    match spec.get("captain") {
+        Some(captain @ JsonValue::Object(_)) => {
+            match captain.get("required") {
+                Some(JsonValue::Bool(required)) => requirements.captain.required = Some(*required),
+                Some(_) => return Err("'captain.required' must be true or false".to_string()),
+                None => {}
+            }
+            match captain.get("formula") {
+                Some(JsonValue::String(formula)) => requirements.captain.formula = Some(formula.clone()),
+                Some(_) => return Err("'captain.formula' must be a string".to_string()),
+                None => {}
+            }
+        }
+        Some(_) => return Err("'captain' must be an object".to_string()),
+        None => {}
+    }
+
+    for (name, value) in object_fields(spec, "constants")? {
+        let JsonValue::Number(value) = value else {
+            return Err(format!("'constants.{}' must be a number", name));
+        };
+        requirements.constants.insert(name.clone(), *value);
+    }
+
+    match spec.get("stamina") {
+        Some(JsonValue::String(formula)) => requirements.stamina = Some(formula.clone()),
+        Some(_) => return Err("'stamina' must be a string".to_string()),
+        None => {}
+    }
+
+    match spec.get("objective") {
+        Some(JsonValue::String(objective)) => requirements.objective = Some(Objective::parse(objective)?),
+        Some(_) => return Err("'objective' must be a string such as \"maximin\"".to_string()),
+        None => {}
+    }
+
+    Ok(())
+}
+
+fn object_fields<'a>(root: &'a JsonValue, key: &str) -> std::result::Result<&'a [(String, JsonValue)], String> {
+    match root.get(key) {
+        Some(JsonValue::Object(fields)) => Ok(fields),
+        Some(_) => Err(format!("'{}' must be an object", key)),
+        None => Ok(&[]),
+    }
+}
+
+// One formation as a single JSON composition, or several under "formations".
+pub fn formations_to_json(formations: &[Formation]) -> JsonValue {
+    let metadata = formations.first()
+        .map(|(_, reqs)| metadata_json(reqs))
+        .unwrap_or(JsonValue::Object(Vec::new()));
+
+    if let [(_, reqs)] = formations {
+        let mut fields = vec![("metadata", metadata)];
+        fields.extend(composition_fields(reqs));
+        return JsonValue::object(fields);
+    }
+
+    JsonValue::object(vec![
+        ("metadata", metadata),
+        ("formations", JsonValue::Object(formations.iter()
+            .map(|(name, reqs)| (name.clone(), JsonValue::object(composition_fields(reqs))))
+            .collect())),
+    ])
+}
+
+fn metadata_json(reqs: &PositionRequirements) -> JsonValue {
+    JsonValue::Object(reqs.metadata.iter()
+        .map(|(k, v)| (k.clone(), JsonValue::string(v)))
+        .collect())
+}
+
+fn composition_fields(reqs: &PositionRequirements) -> Vec<(&'static str, JsonValue)> {
+    let sorted_strings = |map: &HashMap<String, String>| {
+        let mut fields: Vec<(String, JsonValue)> = map.iter()
+            .map(|(k, v)| (k.clone(), JsonValue::string(v)))
+            .collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        JsonValue::Object(fields)
+    };
+
+    let mut weights: Vec<(String, JsonValue)> = reqs.position_weights.iter()
+        .map(|(k, v)| (k.clone(), JsonValue::Number(*v)))
+        .collect();
+    weights.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let slots = |slots: &[String], weights: &[f64]| JsonValue::Array(
+        slots.iter().enumerate().map(|(i, s)| JsonValue::string(&format_slot(s, slot_weight(weights, i)))).collect()
+    );
+
+    let mut fields = vec![
+        ("offense", slots(&reqs.attacking, &reqs.attacking_weights)),
+        ("defense", slots(&reqs.defensive, &reqs.defensive_weights)),
+        ("formulas", sorted_strings(&reqs.position_to_calculation)),
+        ("weights", JsonValue::Object(weights)),
+        ("constraints", sorted_strings(&reqs.eligibility)),
+    ];
+
+    let unit_json = |unit: &UnitSettings| {
+        let mut fields = Vec::new();
+        if let Some(weight) = unit.weight {
+            fields.push(("weight", JsonValue::Number(weight)));
+        }
+        if let Some(minimum) = unit.minimum {
+            fields.push(("minimum", JsonValue::Number(minimum)));
+        }
+        JsonValue::object(fields)
+    };
+    if reqs.offense_unit != UnitSettings::default() || reqs.defense_unit != UnitSettings::default() {
+        fields.push(("units", JsonValue::object(vec![
+            ("offense", unit_json(&reqs.offense_unit)),
+            ("defense", unit_json(&reqs.defense_unit)),
+        ])));
+    }
+
+    if reqs.captain != CaptainSettings::default() {
+        let mut captain = Vec::new();
+        if let Some(required) = reqs.captain.required {
+            captain.push(("required", JsonValue::Bool(required)));
         }
+        if let Some(formula) = &reqs.captain.formula {
+            captain.push(("formula", JsonValue::string(formula)));
+        }
+        fields.push(("captain", JsonValue::object(captain)));
+    }
+
+    if !reqs.constants.is_empty() {
+        let mut constants: Vec<(String, JsonValue)> = reqs.constants.iter()
+            .map(|(k, v)| (k.clone(), JsonValue::Number(*v)))
+            .collect();
+        constants.sort_by(|a, b| a.0.cmp(&b.0));
+        fields.push(("constants", JsonValue::Object(constants)));
     }
 
-    Ok(requirements)
+    if let Some(stamina) = &reqs.stamina {
+        fields.push(("stamina", JsonValue::string(stamina)));
+    }
+
+    if let Some(objective) = reqs.objective {
+        fields.push(("objective", JsonValue::string(&objective.to_string())));
+    }
+
+    fields
+}
+
+pub fn slot_weight(weights: &[f64], index: usize) -> f64 {
+    weights.get(index).copied().unwrap_or(1.0)
+}
+
+// A unit weight or minimum: a non-negative number.
+fn parse_unit_value(text: &str) -> std::result::Result<f64, String> {
+    text.trim().parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .ok_or_else(|| format!("Invalid unit value '{}'", text.trim()))
+}
+
+// Weights and unit settings, like the text format's `parse_unit_value`.
+fn non_negative(value: &JsonValue) -> Option<f64> {
+    match value {
+        JsonValue::Number(n) if n.is_finite() && *n >= 0.0 => Some(*n),
+        _ => None,
+    }
+}
+
+// `RN` or `RN*1.5`: a position and its weight.
+fn parse_slot(token: &str) -> std::result::Result<(String, f64), String> {
+    match token.split_once('*') {
+        Some((position, weight)) => match weight.trim().parse::<f64>() {
+            Ok(weight) if weight.is_finite() && weight >= 0.0 => Ok((position.trim().to_string(), weight)),
+            _ => Err(format!("Invalid weight '{}' for {}", weight.trim(), position.trim())),
+        },
+        None => Ok((token.trim().to_string(), 1.0)),
+    }
+}
+
+fn format_slot(position: &str, weight: f64) -> String {
+    if weight == 1.0 {
+        position.to_string()
+    } else {
+        format!("{}*{}", position, weight)
+    }
 }
 
 fn trim_comment(line: &str) -> &str {
@@ -1,5 +1,6 @@
 // src/evaluator.rs
 
+use std::collections::HashMap;
 use std::str::Chars;
 use crate::player::Player;
 use crate::composition::PositionRequirements;
@@ -10,6 +11,7 @@ use crate::composition::PositionRequirements;
 pub enum EvalError {
     InvalidSyntax(String),
     UnknownStat(String),
+    MissingStat(String),
     UnknownFunction(String),
     DivisionByZero,
     MissingArguments,
@@ -17,11 +19,16 @@ pub enum EvalError {
 
 type EvalResult = Result<f64, EvalError>;
 
-pub fn evaluate(player: &Player, expr: &str) -> EvalResult {
+// Evaluates with the composition's named constants (`const NAME = value`).
+pub fn evaluate_in(player: &Player, expr: &str, reqs: &PositionRequirements) -> EvalResult {
+    evaluate_with_constants(player, expr, &reqs.constants)
+}
+
+fn evaluate_with_constants(player: &Player, expr: &str, constants: &HashMap<String, f64>) -> EvalResult {
 
     // Remove whitespace to greatly simplify parsing!
     let expr = expr.chars().filter(|c| !c.is_whitespace()).collect::<String>();
-    let mut parser = Parser::new(player, &expr);
+    let mut parser = Parser::new(player, constants, &expr);
 
     match parser.parse_expression() {
         Ok(value) => {
@@ -40,14 +47,22 @@ use std::iter::Peekable;
 
 struct Parser<'a> {
     player: &'a Player,
+    // Checked before the player's stats
+    constants: &'a HashMap<String, f64>,
     chars: Peekable<Chars<'a>>,
+    // Inside the first argument of ISMISSING/DEFAULT, where a missing stat
+    // reads as NaN instead of being an error.
+    missing_ok: bool,
+    // Inside the IF branch that isn't taken, where missing stats and division
+    // by zero don't matter because the value is thrown away.
+    skipping: bool,
 }
 
 
 impl<'a> Parser<'a> {
-    fn new(player: &'a Player, expr: &'a str) -> Self {
+    fn new(player: &'a Player, constants: &'a HashMap<String, f64>, expr: &'a str) -> Self {
         let chars = expr.chars().peekable();
-        Parser { player, chars }
+        Parser { player, constants, chars, missing_ok: false, skipping: false }
     }
 
     fn peek(&mut self) -> Option<char> {
@@ -133,23 +148,18 @@ impl<'a> Parser<'a> {
     fn parse_comparison(&mut self) -> EvalResult {
         let left = self.parse_add_sub()?;  // parse lhs expression
     
-        if let Some(op) = self.peek() {
-            match op {
-                '>' | '<' | '=' | '!' => {
-                    let op_str = self.consume_comparison_operator()?;
-                    let right = self.parse_add_sub()?;
-                    return Ok(match op_str.as_str() {
-                        ">"  => (left >  right) as i32 as f64,
-                        ">=" => (left >= right) as i32 as f64,
-                        "<"  => (left <  right) as i32 as f64,
-                        "<=" => (left <= right) as i32 as f64,
-                        "==" => (left == right) as i32 as f64,
-                        "!=" => (left != right) as i32 as f64,
-                        _ => return Err(EvalError::InvalidSyntax(format!("Unknown comparison: {op_str}")))
-                    });
-                }
-                _ => {}
-            }
+        if let Some('>' | '<' | '=' | '!') = self.peek() {
+            let op_str = self.consume_comparison_operator()?;
+            let right = self.parse_add_sub()?;
+            return Ok(match op_str.as_str() {
+                ">"  => (left >  right) as i32 as f64,
+                ">=" => (left >= right) as i32 as f64,
+                "<"  => (left <  right) as i32 as f64,
+                "<=" => (left <= right) as i32 as f64,
+                "==" => (left == right) as i32 as f64,
+                "!=" => (left != right) as i32 as f64,
+                _ => return Err(EvalError::InvalidSyntax(format!("Unknown comparison: {op_str}")))
+            });
         }
     
         Ok(left)
@@ -190,7 +200,7 @@ impl<'a> Parser<'a> {
                 Some('/') => {
                     self.bump();
                     let denom = self.parse_pow()?;
-                    if denom == 0.0 {
+                    if denom == 0.0 && !self.skipping {
                         return Err(EvalError::DivisionByZero);
                     }
                     value /= denom;
@@ -290,14 +300,18 @@ impl<'a> Parser<'a> {
 
         if self.peek() == Some('(') {
             self.bump(); // consume '('
-            let args = self.parse_arguments()?;
+            if name.eq_ignore_ascii_case("IF") {
+                return self.parse_if();
+            }
+            let checks_missing = matches!(name.to_ascii_uppercase().as_str(), "ISMISSING" | "DEFAULT");
+            let args = self.parse_arguments(checks_missing)?;
             self.evaluate_function(&name, args)
         } else {
             self.lookup_stat(&name)
         }
     }
 
-    fn parse_arguments(&mut self) -> Result<Vec<f64>, EvalError> {
+    fn parse_arguments(&mut self, missing_ok_first: bool) -> Result<Vec<f64>, EvalError> {
         let mut args = Vec::new();
 
         loop {
@@ -306,7 +320,11 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            args.push(self.parse_expression()?);
+            let outer_missing_ok = self.missing_ok;
+            self.missing_ok |= missing_ok_first && args.is_empty();
+            let arg = self.parse_expression();
+            self.missing_ok = outer_missing_ok;
+            args.push(arg?);
 
             match self.peek() {
                 Some(',') => {
@@ -327,12 +345,49 @@ impl<'a> Parser<'a> {
         Ok(args)
     }
 
+    // IF(cond, a, b) only evaluates the branch it takes, so
+    // `IF(ISMISSING(Vis), Str, Vis)` works for players without Vis.
+    fn parse_if(&mut self) -> EvalResult {
+        let condition = self.parse_expression()?;
+        let taken = condition.abs() > 0.5;
+
+        self.expect_if_separator(',')?;
+        let then = self.parse_branch(!taken)?;
+        self.expect_if_separator(',')?;
+        let otherwise = self.parse_branch(taken)?;
+        self.expect_if_separator(')')?;
+
+        Ok(if taken { then } else { otherwise })
+    }
+
+    fn parse_branch(&mut self, skipped: bool) -> EvalResult {
+        let outer_skipping = self.skipping;
+        self.skipping |= skipped;
+        let value = self.parse_expression();
+        self.skipping = outer_skipping;
+        value
+    }
+
+    fn expect_if_separator(&mut self, expected: char) -> Result<(), EvalError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            // IF takes exactly three arguments
+            Some(',' | ')') => Err(EvalError::MissingArguments),
+            Some(c) => Err(EvalError::InvalidSyntax(format!("Unexpected character: '{}'", c))),
+            None => Err(EvalError::InvalidSyntax("Unterminated function call".into())),
+        }
+    }
+
     fn evaluate_function(&self, name: &str, args: Vec<f64>) -> EvalResult {
         match name.to_ascii_uppercase().as_str() {
-            "MIN" => args.into_iter().min_by(|a, b| a.partial_cmp(b).unwrap())
-                .ok_or_else(|| EvalError::MissingArguments),
-            "MAX" => args.into_iter().max_by(|a, b| a.partial_cmp(b).unwrap())
-                .ok_or_else(|| EvalError::MissingArguments),
+            // f64::min/max skip NaN, so missing stats are ignored here
+            "MIN" => args.into_iter().reduce(f64::min)
+                .ok_or(EvalError::MissingArguments),
+            "MAX" => args.into_iter().reduce(f64::max)
+                .ok_or(EvalError::MissingArguments),
             "AVERAGE" => {
                 if args.is_empty() {
                     Err(EvalError::MissingArguments)
@@ -340,13 +395,6 @@ impl<'a> Parser<'a> {
                     Ok(args.iter().sum::<f64>() / args.len() as f64)
                 }
             }
-            "IF" => {
-                if args.len() != 3 {
-                    Err(EvalError::MissingArguments)
-                } else {
-                    Ok(if args[0].abs() > 0.5 { args[1] } else { args[2] })
-                }
-            }
             "POW" => {
                 if args.len() != 2 {
                     Err(EvalError::MissingArguments)
@@ -375,16 +423,38 @@ impl<'a> Parser<'a> {
                     Ok(((args[0] != 0.0) || (args[1] != 0.0)) as i32 as f64)
                 }
             }
+            "ISMISSING" => {
+                if args.len() != 1 {
+                    Err(EvalError::MissingArguments)
+                } else {
+                    Ok(args[0].is_nan() as i32 as f64)
+                }
+            }
+            "DEFAULT" => {
+                if args.len() != 2 {
+                    Err(EvalError::MissingArguments)
+                } else {
+                    Ok(if args[0].is_nan() { args[1] } else { args[0] })
+                }
+            }
             
             other => Err(EvalError::UnknownFunction(other.to_string())),
         }
     }
 
     fn lookup_stat(&self, name: &str) -> EvalResult {
+        if let Some((_, value)) = self.constants.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            return Ok(*value);
+        }
+
         
         for (key, value) in &self.player.stats {
             if key.eq_ignore_ascii_case(name) {
-                return Ok(*value as f64);
+                return match value {
+                    Some(value) => Ok(*value),
+                    None if self.missing_ok || self.skipping => Ok(f64::NAN),
+                    None => Err(EvalError::MissingStat(name.to_string())),
+                };
             }
         }
         Err(EvalError::UnknownStat(name.to_string()))
@@ -398,5 +468,5 @@ pub fn evaluate_position(player: &Player, position: &str, reqs: &PositionRequire
         .map(|s| s.as_str())
         .unwrap_or(position); // default to position name if no formula
 
-    evaluate(player, expr)
+    evaluate_in(player, expr, reqs)
 }
\ No newline at end of file
@@ -13,7 +13,7 @@ pub fn open_file(path: &str) -> Result<BufReader<File>> {
     }
 
     let file = File::open(path)?;
-    println!("Loaded file: {}", path);
+    eprintln!("Loaded file: {}", path);
     Ok(BufReader::new(file))
 }
 
@@ -28,21 +28,44 @@ pub fn file_exists(path: &str) -> Result<()> {
     }
 }
 
+// File name without directory or extension, for labelling reports.
+pub fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+pub fn write_report(output_file: Option<&str>, report: &str) -> Result<()> {
+    let mut report = report.to_string();
+    if !report.ends_with('\n') {
+        report.push('\n');
+    }
+
+    match output_file {
+        Some(path) => {
+            fs::write(path, report)?;
+            eprintln!("Saved report: {}", path);
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
 pub fn check_default_files_exist() -> Result<()> {
 
     let c = "composition.txt";
     let t = "team_data.txt";
     
     if let Err(_e) = file_exists(c) {
-        println!("{} not found. Creating default file.", c);
+        eprintln!("{} not found. Creating default file.", c);
         fs::write(c, DEFAULT_COMPOSITION)?;
     }
 
     if let Err(_e) = file_exists(t) {
-        println!("{} not found. Creating default file.", t);
+        eprintln!("{} not found. Creating default file.", t);
         fs::write(t, DEFAULT_TEAM_DATA)?;
-        return Err(Error::new(
-            ErrorKind::Other,
+        return Err(Error::other(
             format!("Please paste your team roster into {}. See the README for further details.", t)
         ));
     }
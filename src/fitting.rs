@@ -0,0 +1,384 @@
+// src/fitting.rs
+
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::composition::PositionRequirements;
+use crate::evaluator::evaluate_in;
+use crate::file_handling::open_file;
+use crate::player::Player;
+
+// How many points a preferred player should score above the other one
+const MARGIN: f64 = 1.0;
+const MAX_ITERATIONS: usize = 100;
+
+// `RN = a*Spd + b*Agl + c*Hnd`: a position formula whose coefficients are free.
+#[derive(Debug, Clone)]
+pub struct LinearTemplate {
+    pub position: String,
+    // (coefficient name, expression it multiplies)
+    pub terms: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Label {
+    Prefer { better: String, worse: String },
+    Target { name: String, score: f64 },
+}
+
+pub struct FitData {
+    pub template: LinearTemplate,
+    pub labels: Vec<Label>,
+}
+
+pub struct FitResult {
+    pub coefficients: Vec<f64>,
+    // False if the set of preferences short of the margin was still changing
+    // after MAX_ITERATIONS re-solves
+    pub converged: bool,
+    // (better, worse, better's score, worse's score) for preferences the fit gets wrong
+    pub unmatched: Vec<(String, String, f64, f64)>,
+    pub preferences: usize,
+    pub targets: usize,
+    pub target_rms: Option<f64>,
+}
+
+pub fn read_fit_labels(path: &str) -> Result<FitData> {
+    let mut text = String::new();
+    open_file(path)?.read_to_string(&mut text)?;
+    parse_fit_labels(&text)
+}
+
+// One `Formula: POS = a*Stat + ...` line, then `Better > Worse`, `Worse < Better`
+// or `Name = score` lines.
+pub fn parse_fit_labels(text: &str) -> Result<FitData> {
+    let mut template = None;
+    let mut labels = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = |msg: String| Error::new(
+            ErrorKind::InvalidData,
+            format!("Label line {}: {}", number + 1, msg),
+        );
+
+        if let Some((keyword, formula)) = line.split_once(':')
+            && keyword.trim().eq_ignore_ascii_case("formula")
+        {
+            if template.is_some() {
+                return Err(invalid("only one formula can be fitted at a time".to_string()));
+            }
+            template = Some(parse_template(formula).map_err(invalid)?);
+        } else if let Some((better, worse)) = line.split_once('>') {
+            labels.push(Label::Prefer { better: better.trim().to_string(), worse: worse.trim().to_string() });
+        } else if let Some((worse, better)) = line.split_once('<') {
+            labels.push(Label::Prefer { better: better.trim().to_string(), worse: worse.trim().to_string() });
+        } else if let Some((name, score)) = line.rsplit_once('=') {
+            let Ok(score) = score.trim().parse::<f64>() else {
+                return Err(invalid(format!("invalid target score '{}'", score.trim())));
+            };
+            labels.push(Label::Target { name: name.trim().to_string(), score });
+        } else {
+            return Err(invalid(format!("expected 'A > B', 'A < B' or 'Name = score', found '{}'", line)));
+        }
+    }
+
+    let Some(template) = template else {
+        return Err(Error::new(ErrorKind::InvalidData, "Label file has no 'Formula:' line"));
+    };
+    if labels.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "Label file has no preferences or target scores"));
+    }
+
+    Ok(FitData { template, labels })
+}
+
+fn parse_template(formula: &str) -> std::result::Result<LinearTemplate, String> {
+    let Some((position, expr)) = formula.split_once('=') else {
+        return Err(format!("expected 'POS = a*Stat + b*Stat', found '{}'", formula.trim()));
+    };
+
+    let mut terms: Vec<(String, String)> = Vec::new();
+    for (subtracted, term) in split_terms(expr) {
+        let Some((coefficient, stat)) = term.split_once('*') else {
+            return Err(format!("term '{}' has no coefficient, expected e.g. 'a*{}'", term, term));
+        };
+        let (coefficient, stat) = (coefficient.trim(), stat.trim());
+
+        if coefficient.is_empty() || !coefficient.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || coefficient.starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Err(format!("invalid coefficient name '{}'", coefficient));
+        }
+        if stat.is_empty() {
+            return Err(format!("coefficient '{}' doesn't multiply anything", coefficient));
+        }
+        if terms.iter().any(|(c, _)| c == coefficient) {
+            return Err(format!("coefficient '{}' is used twice", coefficient));
+        }
+        // `- b*Hnd` fits b on the negated expression, so b stays positive
+        // when the stat counts against the position
+        let stat = if subtracted { format!("-({})", stat) } else { stat.to_string() };
+        terms.push((coefficient.to_string(), stat));
+    }
+
+    Ok(LinearTemplate { position: position.trim().to_string(), terms })
+}
+
+// Splits on `+` and binary `-` outside parentheses. Each term comes with
+// whether it was subtracted; a leading or unary minus stays in its term.
+fn split_terms(expr: &str) -> Vec<(bool, String)> {
+    let mut terms = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    let mut subtracted = false;
+
+    for c in expr.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '+' | '-' if depth == 0 && ends_operand(&current) => {
+                terms.push((subtracted, current.trim().to_string()));
+                current.clear();
+                subtracted = c == '-';
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    terms.push((subtracted, current.trim().to_string()));
+
+    terms.into_iter().filter(|(_, t)| !t.is_empty()).collect()
+}
+
+// Whether a `+` or `-` after `text` is binary, i.e. follows a value rather
+// than the start or another operator.
+fn ends_operand(text: &str) -> bool {
+    text.trim_end().chars().last()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == ')')
+}
+
+// The inside of `-(...)`, the form subtracted terms are stored in.
+fn subtracted_term(expr: &str) -> Option<&str> {
+    let inner = expr.strip_prefix("-(")?.strip_suffix(')')?;
+    let mut depth = 0;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return None,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    Some(inner)
+}
+
+// The value of each term's expression for a labelled player.
+fn features(players: &[Player], reqs: &PositionRequirements, template: &LinearTemplate, name: &str)
+    -> std::result::Result<Vec<f64>, String>
+{
+    let Some(player) = players.iter().find(|p| p.name.eq_ignore_ascii_case(name)) else {
+        return Err(format!("Player '{}' isn't on the roster", name));
+    };
+
+    template.terms.iter()
+        .map(|(_, expr)| evaluate_in(player, expr, reqs)
+            .map_err(|err| format!("Error evaluating {} for {}: {:?}", expr, player.name, err)))
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Least squares on the target scores plus a squared hinge on every preference
+// (the preferred player should score at least MARGIN more). Re-solved with the
+// preferences that are still short of the margin until that set stops changing.
+pub fn fit_coefficients(players: &[Player], reqs: &PositionRequirements, data: &FitData)
+    -> std::result::Result<FitResult, String>
+{
+    let template = &data.template;
+    let k = template.terms.len();
+
+    let mut targets = Vec::new();
+    let mut preferences = Vec::new();
+    for label in &data.labels {
+        match label {
+            Label::Target { name, score } => {
+                targets.push((features(players, reqs, template, name)?, *score));
+            }
+            Label::Prefer { better, worse } => {
+                let (b, w) = (features(players, reqs, template, better)?, features(players, reqs, template, worse)?);
+                let difference: Vec<f64> = b.iter().zip(&w).map(|(x, y)| x - y).collect();
+                preferences.push((better.clone(), worse.clone(), b, w, difference));
+            }
+        }
+    }
+
+    let mut coefficients = vec![0.0; k];
+    let mut active: Option<Vec<bool>> = None;
+    let mut converged = false;
+
+    for _ in 0..MAX_ITERATIONS {
+        let now_active: Vec<bool> = preferences.iter()
+            .map(|(_, _, _, _, d)| dot(&coefficients, d) < MARGIN)
+            .collect();
+        if active.as_ref() == Some(&now_active) {
+            converged = true;
+            break;
+        }
+        let active = active.insert(now_active);
+
+        let rows = targets.iter()
+            .map(|(x, t)| (x, *t))
+            .chain(preferences.iter().zip(active.iter())
+                .filter(|(_, active)| **active)
+                .map(|((_, _, _, _, d), _)| (d, MARGIN)));
+
+        let mut matrix = vec![vec![0.0; k]; k];
+        let mut rhs = vec![0.0; k];
+        for (x, t) in rows {
+            for i in 0..k {
+                rhs[i] += x[i] * t;
+                for j in 0..k {
+                    matrix[i][j] += x[i] * x[j];
+                }
+            }
+        }
+
+        // A little ridge so too few labels still give an answer
+        let trace: f64 = (0..k).map(|i| matrix[i][i]).sum();
+        let ridge = 1e-6 * f64::max(trace / k as f64, 1.0);
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] += ridge;
+        }
+
+        coefficients = solve(matrix, rhs)
+            .ok_or_else(|| "The labels don't pin down the coefficients".to_string())?;
+    }
+
+    // Rankings don't depend on scale, so without targets keep the formula
+    // on the stats' own scale
+    let sum: f64 = coefficients.iter().sum();
+    if targets.is_empty() && sum > 1e-12 {
+        coefficients.iter_mut().for_each(|c| *c /= sum);
+    }
+
+    let unmatched = preferences.iter()
+        .map(|(better, worse, b, w, _)| (better.clone(), worse.clone(), dot(&coefficients, b), dot(&coefficients, w)))
+        .filter(|(_, _, b, w)| b <= w)
+        .collect();
+
+    let target_rms = (!targets.is_empty()).then(|| {
+        let squared: f64 = targets.iter().map(|(x, t)| (dot(&coefficients, x) - t).powi(2)).sum();
+        (squared / targets.len() as f64).sqrt()
+    });
+
+    Ok(FitResult {
+        coefficients,
+        converged,
+        unmatched,
+        preferences: preferences.len(),
+        targets: targets.len(),
+        target_rms,
+    })
+}
+
+// Gaussian elimination with partial pivoting.
+fn solve(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+
+        let pivot_row = matrix[col].clone();
+        for row in col + 1..n {
+            let factor = matrix[row][col] / pivot_row[col];
+            for (value, pivot) in matrix[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|j| matrix[row][j] * solution[j]).sum();
+        solution[row] = (rhs[row] - known) / matrix[row][row];
+    }
+
+    Some(solution)
+}
+
+fn format_coefficient(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+// The template with its coefficients filled in, e.g. `0.5*Spd + 0.3*Agl - 0.1*Hnd`.
+pub fn fitted_formula(template: &LinearTemplate, coefficients: &[f64]) -> String {
+    let mut formula = String::new();
+
+    for (i, ((_, expr), coefficient)) in template.terms.iter().zip(coefficients).enumerate() {
+        // Subtracted terms read back as a subtraction
+        let (expr, coefficient) = match subtracted_term(expr) {
+            Some(inner) => (inner.to_string(), &-coefficient),
+            None => (expr.clone(), coefficient),
+        };
+        let simple = expr.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        let expr = if simple { expr } else { format!("({})", expr) };
+
+        let sign = if *coefficient < 0.0 { "-" } else { "+" };
+        if i > 0 {
+            formula.push_str(&format!(" {} ", sign));
+        } else if sign == "-" {
+            formula.push('-');
+        }
+        formula.push_str(&format!("{}*{}", format_coefficient(coefficient.abs()), expr));
+    }
+
+    formula
+}
+
+pub fn print_fit_report(data: &FitData, result: &FitResult) {
+    let template = &data.template;
+
+    println!(
+        "Fitted {} from {} preference(s) and {} target score(s):\n",
+        template.position, result.preferences, result.targets
+    );
+    for ((name, expr), coefficient) in template.terms.iter().zip(&result.coefficients) {
+        println!("  {:<8} {:>10}  ({})", name, format_coefficient(*coefficient), expr);
+    }
+    println!("\n  {} = {}", template.position, fitted_formula(template, &result.coefficients));
+
+    if result.preferences > 0 {
+        println!(
+            "\nPreferences matched: {} of {}",
+            result.preferences - result.unmatched.len(),
+            result.preferences
+        );
+        for (better, worse, b, w) in &result.unmatched {
+            println!("  Not matched: {} > {} ({:.1} vs {:.1})", better, worse, b, w);
+        }
+    }
+    if let Some(rms) = result.target_rms {
+        println!("Target score error (RMS): {:.2}", rms);
+    }
+    if !result.converged {
+        println!(
+            "Warning: the fit was still changing after {} rounds, so the coefficients may not be the best fit",
+            MAX_ITERATIONS
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// src/formations.rs
+
+use crate::composition::Formation;
+use crate::lineup::{pick_lineup, unit_totals, StartingPosition};
+use crate::pick::to_pick_data;
+use crate::player::Player;
+use crate::report::{print_lineup, print_objective};
+
+pub struct FormationResult {
+    pub name: String,
+    // The lineup and its score, or why the roster can't fill the formation
+    pub lineup: Result<(Vec<StartingPosition>, f64), &'static str>,
+}
+
+// Optimizes the roster under every formation. Highest raw total first, since
+// objective scores of formations with different objectives or weights don't
+// compare; formations the roster can't fill come last.
+pub fn compare_formations(players: &[Player], formations: &[Formation]) -> Vec<FormationResult> {
+    let mut results: Vec<FormationResult> = formations.iter().map(|(name, reqs)| {
+        let pick_data: Vec<_> = players.iter().map(|p| to_pick_data(p, reqs)).collect();
+        let unfilled = if players.len() < reqs.attacking.len() {
+            "not enough players"
+        } else {
+            "no lineup fits the rules"
+        };
+        FormationResult {
+            name: name.clone(),
+            lineup: pick_lineup(&pick_data, reqs).ok_or(unfilled),
+        }
+    }).collect();
+
+    results.sort_by(|a, b| raw_total(b).total_cmp(&raw_total(a)));
+
+    results
+}
+
+fn raw_total(result: &FormationResult) -> f64 {
+    match &result.lineup {
+        Ok((lineup, _)) => {
+            let (offense, defense) = unit_totals(lineup);
+            offense + defense
+        }
+        Err(_) => f64::MIN,
+    }
+}
+
+pub fn print_formation_report(results: &[FormationResult], formations: &[Formation]) {
+    let name_width = results.iter().map(|r| r.name.len()).max().unwrap_or(0) + 3;
+    let objective_of = |name: &str| formations.iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, reqs)| reqs.objective())
+        .unwrap_or_default();
+
+    println!("{:<name_width$}{:>6}{:>6}{:>7}{:>11}", "Formation", "Off", "Def", "Total", "Objective");
+
+    for result in results {
+        match &result.lineup {
+            Ok((lineup, score)) => {
+                let (offense, defense) = unit_totals(lineup);
+                println!(
+                    "{:<name_width$}{:>6.0}{:>6.0}{:>7.0}{:>11.1}",
+                    result.name, offense, defense, offense + defense, score
+                );
+            }
+            Err(unfilled) => println!("{:<name_width$}{:>25}", result.name, unfilled),
+        }
+    }
+
+    let mut objectives = results.iter()
+        .filter(|r| r.lineup.is_ok())
+        .map(|r| objective_of(&r.name));
+    if let Some(first) = objectives.next() && objectives.any(|o| o != first) {
+        println!("\nNote: the formations use different objectives, so their objective scores don't compare.");
+    }
+
+    if let Some(FormationResult { name, lineup: Ok((lineup, score)) }) = results.first() {
+        println!("\nBest formation: {}\n", name);
+        print_lineup(lineup);
+
+        print_objective(objective_of(name), *score);
+    }
+}
@@ -0,0 +1,270 @@
+// src/history.rs
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::composition::PositionRequirements;
+use crate::league::is_team_file;
+use crate::lineup::pick_lineup;
+use crate::pick::to_pick_data;
+use crate::player::Player;
+
+pub const DEFAULT_HISTORY_DIR: &str = "history";
+const COLUMN_WIDTH: usize = 18;
+
+// A recorded roster, labelled by when it was taken, e.g. `2026-10-18_153000`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub label: String,
+    pub path: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StatChange {
+    New,
+    Gone,
+    Changed(Vec<(String, StatDelta)>),
+}
+
+// How one stat moved between snapshots. A stat shown as missing and a stat
+// column that isn't there count the same.
+#[derive(Debug, PartialEq)]
+pub enum StatDelta {
+    By(f64),
+    NowMissing,
+    // Had no value before; the new value
+    New(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgressEntry {
+    pub best_position: String,
+    pub score: f64,
+    // Offense and defense positions, if the player started
+    pub started: Option<(String, String)>,
+}
+
+pub struct PlayerProgress {
+    pub name: String,
+    // One per snapshot, None if the player wasn't on that roster
+    pub entries: Vec<Option<ProgressEntry>>,
+}
+
+// UTC `YYYY-MM-DD_HHMMSS` for seconds since the Unix epoch.
+pub fn timestamp_label(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Days to civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+        year, month, day, time / 3600, time / 60 % 60, time % 60
+    )
+}
+
+// Copies the team data file into `dir` under the current time, keeping its
+// extension so the snapshot reads back in the same format.
+pub fn record_snapshot(team_file: &str, dir: &str) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let label = timestamp_label(seconds);
+    let extension = Path::new(team_file)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_else(|| "txt".to_string());
+
+    let mut path = Path::new(dir).join(format!("{}.{}", label, extension));
+    let mut copy = 2;
+    while path.exists() {
+        // Zero-padded so `_10` still sorts after `_09`
+        path = Path::new(dir).join(format!("{}_{:02}.{}", label, copy, extension));
+        copy += 1;
+    }
+
+    fs::copy(team_file, &path)?;
+    Ok(path)
+}
+
+// Snapshots in `dir`, oldest first.
+pub fn list_snapshots(dir: &str) -> Result<Vec<Snapshot>> {
+    if !Path::new(dir).is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No snapshots yet: {} not found. Record one with --snapshot", dir),
+        ));
+    }
+
+    let mut snapshots: Vec<Snapshot> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_team_file(path))
+        .map(|path| Snapshot {
+            label: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    snapshots.sort_by(|a, b| a.label.cmp(&b.label));
+
+    Ok(snapshots)
+}
+
+// The two snapshots `--deltas` compares, older first. The newer one is the
+// latest snapshot; the older one is the latest whose label starts with
+// `from` (e.g. a date), or the one before the latest. A prefix that matches
+// the latest snapshot compares it with the one before.
+pub fn delta_snapshots<'a>(
+    snapshots: &'a [Snapshot],
+    from: Option<&str>,
+) -> std::result::Result<(&'a Snapshot, &'a Snapshot), String> {
+    let need_two = || format!("Need two snapshots to compare, found {}", snapshots.len());
+    let Some(latest) = snapshots.len().checked_sub(1) else {
+        return Err(need_two());
+    };
+
+    let index = match from {
+        Some(prefix) => match snapshots.iter().rposition(|s| s.label.starts_with(prefix)) {
+            Some(index) => index,
+            None => return Err(format!("No snapshot matches '{}'", prefix)),
+        },
+        None => latest,
+    };
+    let index = if index == latest { index.checked_sub(1).ok_or_else(need_two)? } else { index };
+
+    Ok((&snapshots[index], &snapshots[latest]))
+}
+
+// Stat changes per player from `before` to `after`, in `after`'s roster order
+// with players who left at the end.
+pub fn stat_deltas(before: &[Player], after: &[Player]) -> Vec<(String, StatChange)> {
+    let mut deltas: Vec<(String, StatChange)> = after.iter().map(|player| {
+        let Some(old) = before.iter().find(|p| p.name == player.name) else {
+            return (player.name.clone(), StatChange::New);
+        };
+
+        let value = |p: &Player, stat: &String| p.stats.get(stat).copied().flatten();
+
+        let mut stats: Vec<&String> = player.stats.keys().chain(old.stats.keys()).collect();
+        stats.sort();
+        stats.dedup();
+
+        let changes: Vec<(String, StatDelta)> = stats.into_iter()
+            .filter_map(|stat| {
+                let delta = match (value(old, stat), value(player, stat)) {
+                    (Some(old), Some(new)) if new != old => StatDelta::By(new - old),
+                    (Some(_), None) => StatDelta::NowMissing,
+                    (None, Some(new)) => StatDelta::New(new),
+                    _ => return None,
+                };
+                Some((stat.clone(), delta))
+            })
+            .collect();
+
+        (player.name.clone(), StatChange::Changed(changes))
+    }).collect();
+
+    deltas.extend(before.iter()
+        .filter(|old| !after.iter().any(|p| p.name == old.name))
+        .map(|old| (old.name.clone(), StatChange::Gone)));
+
+    deltas
+}
+
+pub fn print_delta_report(from: &str, to: &str, deltas: &[(String, StatChange)]) {
+    println!("Stat changes from {} to {}\n", from, to);
+
+    let width = deltas.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(6);
+    println!("{:<width$}  Changes", "Player");
+
+    for (name, change) in deltas {
+        let text = match change {
+            StatChange::New => "(new)".to_string(),
+            StatChange::Gone => "(gone)".to_string(),
+            StatChange::Changed(changes) if changes.is_empty() => "-".to_string(),
+            StatChange::Changed(changes) => changes.iter()
+                .map(|(stat, delta)| match delta {
+                    StatDelta::By(delta) => format!("{} {:+}", stat, delta),
+                    StatDelta::NowMissing => format!("{} (now missing)", stat),
+                    StatDelta::New(value) => format!("{} {} (new)", stat, value),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        println!("{:<width$}  {}", name, text);
+    }
+}
+
+// Scores and lineup status of every player at each snapshot, strongest
+// players in the latest snapshot first.
+pub fn progression(rosters: &[Vec<Player>], reqs: &PositionRequirements) -> Vec<PlayerProgress> {
+    let mut progress: Vec<PlayerProgress> = Vec::new();
+
+    for (i, players) in rosters.iter().enumerate() {
+        let pick_data: Vec<_> = players.iter().map(|p| to_pick_data(p, reqs)).collect();
+        let lineup = pick_lineup(&pick_data, reqs).map(|(lineup, _)| lineup).unwrap_or_default();
+
+        for data in &pick_data {
+            let (best_position, score) = data.position_scores.iter()
+                .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(pos, score)| (pos.clone(), *score))
+                .unwrap_or_default();
+            let started = lineup.iter()
+                .find(|s| s.name == data.name)
+                .map(|s| (s.offense.position.clone(), s.defense.position.clone()));
+
+            let index = match progress.iter().position(|p| p.name == data.name) {
+                Some(index) => index,
+                None => {
+                    progress.push(PlayerProgress { name: data.name.clone(), entries: vec![None; rosters.len()] });
+                    progress.len() - 1
+                }
+            };
+            progress[index].entries[i] = Some(ProgressEntry { best_position, score, started });
+        }
+    }
+
+    let latest_score = |p: &PlayerProgress| p.entries.last().cloned().flatten().map(|e| e.score).unwrap_or(f64::MIN);
+    progress.sort_by(|a, b| latest_score(b).total_cmp(&latest_score(a)));
+    progress
+}
+
+pub fn print_progress_report(snapshots: &[Snapshot], progress: &[PlayerProgress]) {
+    let width = progress.iter().map(|p| p.name.len()).max().unwrap_or(0).max(6);
+
+    println!("Best position score and lineup per snapshot (RN/DL = started there, - = bench)\n");
+
+    let mut header = format!("{:<width$}", "Player");
+    for snapshot in snapshots {
+        header.push_str(&format!("  {:<COLUMN_WIDTH$}", snapshot.label));
+    }
+    println!("{}", header.trim_end());
+
+    for player in progress {
+        let mut row = format!("{:<width$}", player.name);
+        for entry in &player.entries {
+            let cell = match entry {
+                None => String::new(),
+                Some(entry) => {
+                    let status = match &entry.started {
+                        Some((offense, defense)) => format!("{}/{}", offense, defense),
+                        None => "-".to_string(),
+                    };
+                    format!("{} {:.0} {}", entry.best_position, entry.score, status)
+                }
+            };
+            row.push_str(&format!("  {:<COLUMN_WIDTH$}", cell));
+        }
+        println!("{}", row.trim_end());
+    }
+}
@@ -0,0 +1,166 @@
+// src/league.rs
+
+use std::fs;
+use std::io::Result;
+use std::path::Path;
+
+use crate::composition::PositionRequirements;
+use crate::lineup::{explain_infeasible, pick_lineup, StartingPosition};
+use crate::pick::to_pick_data;
+use crate::roster::read_roster;
+
+#[derive(Debug, Clone)]
+pub struct LeagueEntry {
+    pub team: String,
+    pub offense: f64,
+    pub defense: f64,
+    pub positions: Vec<(String, f64)>,
+}
+
+impl LeagueEntry {
+    pub fn total(&self) -> f64 {
+        self.offense + self.defense
+    }
+}
+
+// Optimizes every team data file in `dir` with the same composition and
+// returns them strongest first.
+pub fn league_table(dir: &str, reqs: &PositionRequirements) -> Result<Vec<LeagueEntry>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_team_file(path))
+        .collect();
+    paths.sort();
+
+    let mut table = Vec::new();
+
+    for path in paths {
+        let path_str = path.to_string_lossy().to_string();
+        let team = path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| path_str.clone());
+
+        // One bad file shouldn't cost the rest of the table
+        let players = match read_roster(&path_str) {
+            Ok(players) => players,
+            Err(e) => {
+                eprintln!("Warning: skipping '{}': {}", team, e);
+                continue;
+            }
+        };
+        let pick_data: Vec<_> = players.iter().map(|p| to_pick_data(p, reqs)).collect();
+
+        match pick_lineup(&pick_data, reqs) {
+            Some((lineup, _)) => table.push(summarize(&team, &lineup, reqs)),
+            // Too few players, or the rules rule out every lineup
+            None => eprintln!(
+                "Warning: skipping '{}': {}",
+                team,
+                explain_infeasible(&pick_data, reqs).join("; ")
+            ),
+        }
+    }
+
+    table.sort_by(|a, b| b.total().partial_cmp(&a.total()).unwrap());
+    Ok(table)
+}
+
+pub fn is_team_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy().to_lowercase();
+        matches!(ext.as_str(), "txt" | "csv" | "tsv" | "json" | "html" | "htm")
+    })
+}
+
+fn summarize(team: &str, lineup: &[StartingPosition], reqs: &PositionRequirements) -> LeagueEntry {
+    let mut positions: Vec<(String, f64)> = Vec::new();
+    for pos in reqs.attacking.iter().chain(&reqs.defensive) {
+        if !positions.iter().any(|(p, _)| p == pos) {
+            positions.push((pos.clone(), 0.0));
+        }
+    }
+
+    for starter in lineup {
+        for side in [&starter.offense, &starter.defense] {
+            if let Some((_, total)) = positions.iter_mut().find(|(p, _)| *p == side.position) {
+                *total += side.score;
+            }
+        }
+    }
+
+    LeagueEntry {
+        team: team.to_string(),
+        offense: lineup.iter().map(|s| s.offense.score).sum(),
+        defense: lineup.iter().map(|s| s.defense.score).sum(),
+        positions,
+    }
+}
+
+pub fn format_league_text(table: &[LeagueEntry]) -> String {
+    let mut out = String::new();
+
+    if table.is_empty() {
+        out.push_str("No team data files found.\n");
+        return out;
+    }
+
+    let team_width = table.iter().map(|e| e.team.len()).max().unwrap_or(0) + 3;
+
+    out.push_str(&format!("{:<4}{:<team_width$}{:>6}{:>6}{:>7}", "#", "Team", "Off", "Def", "Total"));
+    for (pos, _) in &table[0].positions {
+        out.push_str(&format!("{:>6}", pos));
+    }
+    out.push('\n');
+
+    for (rank, entry) in table.iter().enumerate() {
+        out.push_str(&format!(
+            "{:<4}{:<team_width$}{:>6.0}{:>6.0}{:>7.0}",
+            rank + 1,
+            entry.team,
+            entry.offense,
+            entry.defense,
+            entry.total()
+        ));
+        for (_, score) in &entry.positions {
+            out.push_str(&format!("{:>6.0}", score));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn format_league_csv(table: &[LeagueEntry]) -> String {
+    let mut out = String::from("Rank,Team,Offense,Defense,Total");
+    if let Some(first) = table.first() {
+        for (pos, _) in &first.positions {
+            out.push_str(&format!(",{}", pos));
+        }
+    }
+    out.push('\n');
+
+    for (rank, entry) in table.iter().enumerate() {
+        out.push_str(&format!(
+            "{},{},{:.1},{:.1},{:.1}",
+            rank + 1,
+            csv_field(&entry.team),
+            entry.offense,
+            entry.defense,
+            entry.total()
+        ));
+        for (_, score) in &entry.positions {
+            out.push_str(&format!(",{:.1}", score));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -1,35 +1,109 @@
 // src/lineup.rs
 
-use std::collections::HashSet;
-
+use crate::assignment::best_assignment;
 use crate::pick::PickTempData;
 
 #[derive(Debug, Clone)]
 pub struct PositionDescription {
     pub position: String,
     pub score: f64,
+    // Position weight times slot weight; the optimizer counts score * weight
+    pub weight: f64,
+}
+
+impl PositionDescription {
+    pub fn weighted_score(&self) -> f64 {
+        self.score * self.weight
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct StartingPosition {
     pub name: String,
+    // Where the player is in the pick data `pick_lineup` was given, which
+    // tells players with the same name apart
+    pub index: usize,
     pub offense: PositionDescription,
     pub defense: PositionDescription,
     pub total_score: f64,
+    pub captain: bool,
 }
 
+// Sorts the pick data by best potential, takes the top players as the initial
+// lineup and optimizes from there. Returns the lineup with its objective score,
+// without the optimizer's penalties, or `None` if the slots can't be filled.
+pub fn pick_lineup(
+    all_players: &[PickTempData],
+    reqs: &PositionRequirements,
+) -> Option<(Vec<StartingPosition>, f64)> {
+    let team_size = reqs.attacking.len();
+    if team_size == 0 || all_players.len() < team_size {
+        return None;
+    }
+
+    // Indices into `all_players`, best potential first
+    let mut sorted: Vec<usize> = (0..all_players.len()).collect();
+    sorted.sort_by(|&a, &b| all_players[b].max_score.total_cmp(&all_players[a].max_score));
+
+    let mut initial = sorted[..team_size].to_vec();
+
+    // Start the captain so the optimizer only has to keep them in. Without a
+    // captain on the roster the requirement can't apply.
+    let unrequired;
+    let reqs = match sorted.iter().find(|&&i| all_players[i].captain) {
+        Some(&captain) if reqs.captain.required() => {
+            if !initial.iter().any(|&i| all_players[i].captain) {
+                initial[team_size - 1] = captain;
+            }
+            reqs
+        }
+        None if reqs.captain.required() => {
+            unrequired = PositionRequirements {
+                captain: CaptainSettings { required: Some(false), ..reqs.captain.clone() },
+                ..reqs.clone()
+            };
+            &unrequired
+        }
+        _ => reqs,
+    };
+
+    let (lineup, _) = optimize_lineup(all_players, &sorted, initial, reqs);
 
+    // No combination of players satisfies the eligibility rules, or none of
+    // them can include the captain
+    if lineup.is_empty() || (reqs.captain.required() && !lineup.iter().any(|s| s.captain)) {
+        return None;
+    }
 
+    let score = lineup_score(&lineup, reqs);
+    Some((lineup, score))
+}
+
+// The objective score of a lineup without any penalties, so the scores of
+// different lineups can be compared.
+pub fn lineup_score(lineup: &[StartingPosition], reqs: &PositionRequirements) -> f64 {
+    let offense = lineup.iter().map(|s| s.offense.weighted_score()).sum();
+    let defense = lineup.iter().map(|s| s.defense.weighted_score()).sum();
+    let slots: Vec<f64> = lineup.iter()
+        .flat_map(|s| [s.offense.weighted_score(), s.defense.weighted_score()])
+        .collect();
+
+    reqs.objective().score(offense, defense, &slots)
+}
+
+// Players are indices into `all_players`; `order` is the order bench players
+// are tried in.
 pub fn optimize_lineup(
     all_players: &[PickTempData],
-    initial_lineup: Vec<PickTempData>,
+    order: &[usize],
+    initial_lineup: Vec<usize>,
     reqs: &PositionRequirements,
 ) -> (Vec<StartingPosition>, f64) {
     let mut starters = initial_lineup;
     let mut best_score;
 
     // Initial evaluation
-    let (mut best_lineup, score) = get_initial_lineup(&starters, reqs);
+    let (mut best_lineup, score) = get_initial_lineup(all_players, &starters, reqs);
     best_score = score;
 
     let mut improved = true;
@@ -37,18 +111,16 @@ pub fn optimize_lineup(
     while improved {
         improved = false;
 
-        let starter_names: Vec<String> = starters.iter().map(|p| p.name.clone()).collect();
-
-        for bench_player in all_players {
-            if starter_names.contains(&bench_player.name) {
+        for &bench_player in order {
+            if starters.contains(&bench_player) {
                 continue;
             }
 
             for i in 0..starters.len() {
                 let mut trial = starters.clone();
-                trial[i] = bench_player.clone();
+                trial[i] = bench_player;
 
-                let (trial_lineup, trial_score) = get_initial_lineup(&trial, reqs);
+                let (trial_lineup, trial_score) = get_initial_lineup(all_players, &trial, reqs);
 
                 if trial_score > best_score {
                     best_score = trial_score;
@@ -69,89 +141,224 @@ pub fn optimize_lineup(
 }
 
 fn find_best_positions(
-    players: &[PickTempData],
+    players: &[&PickTempData],
     positions: &[String],
+    slot_weights: &[f64],
+    unit_weight: f64,
+    reqs: &PositionRequirements,
 ) -> (Vec<(String, PositionDescription)>, f64) {
     assert!(players.len() == positions.len());
 
-    if players.is_empty() {
-        return (Vec::new(), 0.0);
+    // Collapse duplicate slots so each distinct (position, weight) is only
+    // tried once per player
+    let mut distinct: Vec<(&String, f64)> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+    for (i, pos) in positions.iter().enumerate() {
+        let slot = (pos, unit_weight * reqs.weight(pos) * slot_weight(slot_weights, i));
+        match distinct.iter().position(|d| *d == slot) {
+            Some(index) => counts[index] += 1,
+            None => {
+                distinct.push(slot);
+                counts.push(1);
+            }
+        }
     }
 
-    let first = &players[0];
-    let rest = &players[1..];
-
-    let mut best_score = f64::MIN;
-    let mut best_result = Vec::new();
+    // Look every score up once instead of hashing position names in the search
+    let scores: Vec<Vec<Option<f64>>> = players.iter()
+        .map(|p| distinct.iter().map(|(pos, _)| p.position_scores.get(*pos).copied()).collect())
+        .collect();
 
-    let mut tried = HashSet::new();
+    // The search maximizes the weighted total; the lineup reports raw scores
+    let weighted: Vec<Vec<Option<f64>>> = scores.iter()
+        .map(|row| row.iter().zip(&distinct).map(|(s, (_, weight))| s.map(|s| s * weight)).collect())
+        .collect();
 
-    for pos in positions {
-        if tried.contains(pos) {
-            continue;
-        }
-        tried.insert(pos.clone());
+    let Some((choices, best_score)) = best_assignment(&weighted, &counts) else {
+        return (Vec::new(), f64::MIN);
+    };
 
-        if let Some(&score) = first.position_scores.get(pos) {
-            let mut remaining = positions.to_vec();
-            if let Some(index) = remaining.iter().position(|p| p == pos) {
-                remaining.remove(index);
-            }
-
-            let (sub_result, sub_score) = find_best_positions(rest, &remaining);
-            let total = score + sub_score;
-
-            if total > best_score {
-                let entry = (first.name.clone(), PositionDescription {
-                    position: pos.clone(),
-                    score,
-                });
-                best_result = sub_result;
-                best_result.push(entry);
-                best_score = total;
-            }
-        }
-    }
+    let best_result = players.iter().zip(choices).enumerate().map(|(i, (player, choice))| {
+        (player.name.clone(), PositionDescription {
+            position: distinct[choice].0.clone(),
+            score: scores[i][choice].unwrap_or(0.0),
+            weight: distinct[choice].1,
+        })
+    }).collect();
 
     (best_result, best_score)
 }
 
+// `offense` and `defense` are in the same order as the starters.
 fn make_lineup(
-    starters: &[PickTempData],
+    indices: &[usize],
+    starters: &[&PickTempData],
     offense: &[(String, PositionDescription)],
     defense: &[(String, PositionDescription)],
 ) -> Vec<StartingPosition> {
-    starters.iter().map(|p| {
-        let offense_pos = offense.iter().find(|(name, _)| name == &p.name).unwrap().1.clone();
-        let defense_pos = defense.iter().find(|(name, _)| name == &p.name).unwrap().1.clone();
+    starters.iter().enumerate().map(|(i, p)| {
+        let offense_pos = offense[i].1.clone();
+        let defense_pos = defense[i].1.clone();
 
         StartingPosition {
             name: p.name.clone(),
+            index: indices[i],
             offense: offense_pos.clone(),
             defense: defense_pos.clone(),
             total_score: offense_pos.score + defense_pos.score,
+            captain: p.captain,
         }
     }).collect()
 }
 
-use crate::composition::PositionRequirements;
+use crate::composition::{slot_weight, CaptainSettings, PositionRequirements};
 
 fn get_initial_lineup(
-    all: &[PickTempData],
+    all_players: &[PickTempData],
+    indices: &[usize],
     reqs: &PositionRequirements,
 ) -> (Vec<StartingPosition>, f64) {
     let team_size = reqs.attacking.len();
     assert_eq!(team_size, reqs.defensive.len());
 
-    let starters = &all[..team_size];
+    let indices = &indices[..team_size];
+    let starters: Vec<&PickTempData> = indices.iter().map(|&i| &all_players[i]).collect();
+    let starters = starters.as_slice();
+
+    let (offense, _) = find_best_positions(
+        starters, &reqs.attacking, &reqs.attacking_weights, reqs.offense_unit.weight(), reqs,
+    );
+    let (defense, _) = find_best_positions(
+        starters, &reqs.defensive, &reqs.defensive_weights, reqs.defense_unit.weight(), reqs,
+    );
+
+    // Somebody couldn't be placed (eligibility rules); worse than any real
+    // lineup, but less bad the fewer places are missing so swaps can get there
+    if offense.len() < team_size || defense.len() < team_size {
+        let missing = eligibility_deficit(starters, &reqs.attacking)
+            + eligibility_deficit(starters, &reqs.defensive);
+        return (Vec::new(), INFEASIBLE_PENALTY * missing.max(1) as f64);
+    }
+
+    let lineup = make_lineup(indices, starters, &offense, &defense);
+
+    if reqs.captain.required() && !lineup.iter().any(|s| s.captain) {
+        return (lineup, CAPTAIN_PENALTY);
+    }
+
+    let score = lineup_score(&lineup, reqs) - MINIMUM_PENALTY * minimum_shortfall(&lineup, reqs);
 
-    let (offense, offense_score) = find_best_positions(starters, &reqs.attacking);
-    let (defense, defense_score) = find_best_positions(starters, &reqs.defensive);
+    (lineup, score)
+}
 
-    let total_score = offense_score + defense_score;
-    let lineup = make_lineup(starters, &offense, &defense);
+// A lineup without the required captain; still better than one that can't
+// be filled at all
+const CAPTAIN_PENALTY: f64 = -1e9;
+
+// Per slot the starters can't fill because nobody eligible is left for it
+const INFEASIBLE_PENALTY: f64 = -1e12;
+
+// Slots, summed over positions, with fewer eligible starters than needed.
+fn eligibility_deficit(starters: &[&PickTempData], positions: &[String]) -> usize {
+    position_counts(positions).into_iter()
+        .map(|(pos, needed)| {
+            let eligible = starters.iter().filter(|p| p.position_scores.contains_key(pos)).count();
+            needed.saturating_sub(eligible)
+        })
+        .sum()
+}
 
-    (lineup, total_score)
+// Each distinct position with how many slots it has, in slot order.
+fn position_counts(positions: &[String]) -> Vec<(&String, usize)> {
+    let mut counts: Vec<(&String, usize)> = Vec::new();
+    for pos in positions {
+        match counts.iter_mut().find(|(p, _)| *p == pos) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((pos, 1)),
+        }
+    }
+    counts
 }
 
+// Why `pick_lineup` found nothing: too few players, or one line per position
+// with fewer eligible players on the whole roster than it has slots.
+pub fn explain_infeasible(all_players: &[PickTempData], reqs: &PositionRequirements) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    if reqs.attacking.is_empty() {
+        reasons.push("The composition has no slots to fill".to_string());
+        return reasons;
+    }
+    if all_players.len() < reqs.attacking.len() {
+        reasons.push(format!(
+            "Only {} player(s) for {} lineup places",
+            all_players.len(),
+            reqs.attacking.len()
+        ));
+        return reasons;
+    }
 
+    for (unit, positions) in [("offense", &reqs.attacking), ("defense", &reqs.defensive)] {
+        for (pos, needed) in position_counts(positions) {
+            let eligible = all_players.iter().filter(|p| p.position_scores.contains_key(pos)).count();
+            if eligible < needed {
+                let rule = reqs.eligibility.get(pos)
+                    .map(|rule| format!(" ({} requires {})", pos, rule))
+                    .unwrap_or_default();
+                reasons.push(format!(
+                    "{}: {} {} slot(s) but only {} eligible player(s){}",
+                    pos, needed, unit, eligible, rule
+                ));
+            }
+        }
+    }
+
+    if reasons.is_empty() && reqs.captain.required()
+        && let Some(captain) = all_players.iter().find(|p| p.captain)
+    {
+        let eligible = |positions: &[String]| positions.iter().any(|pos| captain.position_scores.contains_key(pos));
+        let unit = match (eligible(&reqs.attacking), eligible(&reqs.defensive)) {
+            (false, _) => Some("offense"),
+            (true, false) => Some("defense"),
+            (true, true) => None,
+        };
+        reasons.push(match unit {
+            Some(unit) => format!("The captain, {}, can't fill any eligible {} slot", captain.name, unit),
+            None => format!(
+                "The captain, {}, can't start in any lineup that fills every eligible slot",
+                captain.name
+            ),
+        });
+    }
+
+    if reasons.is_empty() {
+        reasons.push(
+            "Every position has enough eligible players, but no group of starters \
+            can cover all offensive and defensive slots at once".to_string()
+        );
+    }
+
+    reasons
+}
+
+// Per point a unit total falls below its minimum. Large enough that meeting
+// the minimums beats any score gain, but still lets the optimizer climb
+// towards them.
+const MINIMUM_PENALTY: f64 = 1000.0;
+
+// How far the raw offense and defense totals fall short of the composition's
+// unit minimums, combined.
+pub fn minimum_shortfall(lineup: &[StartingPosition], reqs: &PositionRequirements) -> f64 {
+    let (offense, defense) = unit_totals(lineup);
+    let short = |total: f64, minimum: Option<f64>| minimum.map_or(0.0, |m| (m - total).max(0.0));
+
+    short(offense, reqs.offense_unit.minimum) + short(defense, reqs.defense_unit.minimum)
+}
+
+// Raw (unweighted) offense and defense totals.
+pub fn unit_totals(lineup: &[StartingPosition]) -> (f64, f64) {
+    (
+        lineup.iter().map(|s| s.offense.score).sum(),
+        lineup.iter().map(|s| s.defense.score).sum(),
+    )
+}
@@ -1,116 +1,338 @@
 // src/main.rs
 use std::io::{self, Write};
 
+mod assignment;
+mod availability;
+mod break_even;
+mod captain;
 mod cli;
+mod compare;
 mod composition;
 mod evaluator;
 mod file_handling;
+mod fitting;
+mod formations;
+mod history;
+mod json;
+mod league;
 mod lineup;
+mod objective;
 mod pick;
 mod player;
+mod release;
+mod report;
 mod roster;
+mod roster_html;
+mod sensitivity;
+mod simulation;
+mod rotation;
 mod testing;
+mod training;
+mod transfer;
 
+use availability::{read_availability, Availability};
+use break_even::{find_break_even, print_break_even_report, Improvement};
+use captain::print_captain_report;
 use cli::*;
-use composition::parse_composition;
-use lineup::optimize_lineup;
+use compare::{compare_lineups, comparison_to_json, format_comparison_text};
+use composition::{formations_to_json, parse_formations, select_formation, shadowed_stats};
+use history::{
+    delta_snapshots, list_snapshots, print_delta_report, print_progress_report, progression,
+    record_snapshot, stat_deltas,
+};
+use league::{format_league_csv, format_league_text, league_table};
+use lineup::{explain_infeasible, pick_lineup};
 use pick::to_pick_data;
-use roster::read_roster;
-use file_handling::check_default_files_exist;
+use release::{marginal_values, print_release_report};
+use report::{print_lineup, print_objective, print_unit_minimums};
+use player::Player;
+use roster::{read_roster, read_roster_as, roster_to_json, RosterFormat};
+use rotation::{plan_rotation, print_rotation_report};
+use sensitivity::{print_sweep_report, sweep_constant, sweep_values};
+use simulation::{print_simulation_report, simulate, SimulationSettings};
+use file_handling::{check_default_files_exist, file_stem, write_report};
+use fitting::{fit_coefficients, fitted_formula, print_fit_report, read_fit_labels};
+use formations::{compare_formations, print_formation_report};
+use training::{print_training_report, recommend_training};
+use transfer::{evaluate_transfers, print_transfer_report};
 
+// The prompt goes to stderr so reports on stdout stay machine-readable.
 fn pause() {
-    print!("\nPress 'Enter' to quit.");
-    io::stdout().flush().unwrap();
+    eprint!("\nPress 'Enter' to quit.");
+    io::stderr().flush().unwrap();
     io::stdin().read_line(&mut String::new()).unwrap();
 }
 
+fn read_team(path: &str, format: Option<RosterFormat>) -> io::Result<Vec<Player>> {
+    match format {
+        Some(format) => read_roster_as(path, format),
+        None => read_roster(path),
+    }
+}
+
 fn main() -> io::Result<()> {
     let config = match from_args() {
         ArgParseResult::Exit => return Ok(()),
-        ArgParseResult::Config(config) => config,
+        ArgParseResult::Config(config) => *config,
     };
     
     // Create default files if they don't exist
-    if config.using_defaults {
-        if let Err(e) = check_default_files_exist() {
-            println!("{e}");
+    if config.using_defaults && let Err(e) = check_default_files_exist() {
+        eprintln!("{e}");
+        pause();
+        return Ok(());
+    }
+
+    let formations = parse_formations(&config.comp_file)?;
+
+    if let Mode::ConvertComposition = config.mode {
+        write_report(config.output_file.as_deref(), &formations_to_json(&formations).to_pretty_string())?;
+        pause();
+        return Ok(());
+    }
+
+    let composition = select_formation(formations.clone(), config.formation.as_deref())?;
+
+    // League mode works on a directory of teams rather than our own roster
+    if let Mode::League { dir } = &config.mode {
+        let table = league_table(dir, &composition)?;
+        let report = match config.output_format {
+            OutputFormat::Csv => format_league_csv(&table),
+            _ => format_league_text(&table),
+        };
+        write_report(config.output_file.as_deref(), &report)?;
+        pause();
+        return Ok(());
+    }
+
+    // History modes work on the snapshot directory
+    match &config.mode {
+        Mode::Snapshot => {
+            let path = record_snapshot(&config.team_file, &config.history_dir)?;
+            println!("Saved snapshot: {}", path.display());
+            pause();
+            return Ok(());
+        }
+        Mode::Deltas { from } => {
+            let snapshots = list_snapshots(&config.history_dir)?;
+            match delta_snapshots(&snapshots, from.as_deref()) {
+                Ok((from, to)) => {
+                    let before = read_team(&from.path, config.roster_format)?;
+                    let after = read_team(&to.path, config.roster_format)?;
+                    print_delta_report(&from.label, &to.label, &stat_deltas(&before, &after));
+                }
+                Err(msg) => println!("{} in {}", msg, config.history_dir),
+            }
+            pause();
+            return Ok(());
+        }
+        Mode::Progress => {
+            let snapshots = list_snapshots(&config.history_dir)?;
+            let rosters = snapshots.iter()
+                .map(|s| read_team(&s.path, config.roster_format))
+                .collect::<io::Result<Vec<_>>>()?;
+            print_progress_report(&snapshots, &progression(&rosters, &composition));
             pause();
             return Ok(());
         }
+        _ => {}
     }
 
-    let composition = parse_composition(&config.comp_file)?;
-    let players = read_roster(&config.team_file)?;
+    let players = read_team(&config.team_file, config.roster_format)?;
+    for name in shadowed_stats(&formations, &players) {
+        eprintln!("Warning: constant '{}' has the same name as a roster stat, so formulas use the constant instead", name);
+    }
+
+    if let Mode::ExportJson = config.mode {
+        write_report(config.output_file.as_deref(), &roster_to_json(&players).to_pretty_string())?;
+        pause();
+        return Ok(());
+    }
 
-    if players.len() < composition.attacking.len() {
-        println!(
-            "Not enough players in team_data.txt.\n\
+    let availability = match &config.availability_file {
+        Some(path) => read_availability(path)?,
+        None => Availability::default(),
+    };
+    // On stderr, like the other diagnostics, so --json reports stay valid
+    for line in availability.summary(&players, config.doubtful) {
+        eprintln!("{}", line);
+    }
+    if !availability.statuses.is_empty() {
+        eprintln!();
+    }
+
+    let rostered = players.len();
+    let players = availability.apply_doubtful(players, config.doubtful);
+    // The rotation planner brings injured players back when they're fit, and
+    // fitting only looks at labelled players
+    let players = match config.mode {
+        Mode::Rotation { .. } | Mode::Fit { .. } => players,
+        _ => availability.remove_unavailable(players),
+    };
+
+    // Fitting doesn't pick a lineup, so any number of players will do
+    if !matches!(config.mode, Mode::Fit { .. }) && players.len() < composition.attacking.len() {
+        let excluded = rostered - players.len();
+        eprintln!(
+            "Not enough players in {}.\n\
             Found {}, but at least {} are required.",
+            config.team_file,
             players.len(),
             composition.attacking.len()
         );
-        println!("\nPlease paste your team roster into team_data.txt. See the README for further details.");
+        if excluded > 0 {
+            eprintln!("{} more are excluded as unavailable in the availability file.", excluded);
+        } else {
+            eprintln!("\nPlease paste your team roster into {}. See the README for further details.", config.team_file);
+        }
         pause();
         return Ok(());
     }
 
-    let mut all_pick_data: Vec<_> = players.iter()
-        .map(|p| to_pick_data(p, &composition))
-        .collect();
+    match config.mode {
+        Mode::Pick => {
+            let all_pick_data: Vec<_> = players.iter()
+                .map(|p| to_pick_data(p, &composition))
+                .collect();
 
-    // Sort by best potential
-    all_pick_data.sort_by(|a, b| b.max_score.partial_cmp(&a.max_score).unwrap());
+            if let Some(name) = composition.name() {
+                println!("Composition: {}\n", name);
+            }
 
-    let team_size = composition.attacking.len();
-    let initial = all_pick_data[..team_size].to_vec();
+            if let Some((optimized_team, score)) = pick_lineup(&all_pick_data, &composition) {
+                print_lineup(&optimized_team);
+                print_objective(composition.objective(), score);
+                print_unit_minimums(&optimized_team, &composition);
+                print_captain_report(&players, &optimized_team, &composition);
+            } else {
+                println!("No lineup satisfies the composition's eligibility rules:");
+                for reason in explain_infeasible(&all_pick_data, &composition) {
+                    println!("  {}", reason);
+                }
+            }
+        }
+        Mode::Train { points } => {
+            let options = recommend_training(&players, &composition, points);
+            print_training_report(&options, points);
+        }
+        Mode::BreakEven { stat } => {
+            let improvement = match stat {
+                Some(stat) => Improvement::Stat(stat),
+                None => Improvement::PositionScore,
+            };
+            match find_break_even(&players, &composition, &improvement) {
+                Some(results) => print_break_even_report(&results, &improvement),
+                None => println!("No lineup satisfies the composition, so there's no lineup to break into."),
+            }
+        }
+        Mode::Transfer { candidates_file, price_column } => {
+            let candidates = read_team(&candidates_file, config.roster_format)?;
+            let options = evaluate_transfers(&players, &candidates, &composition, price_column.as_deref());
+            print_transfer_report(&options, price_column.as_deref());
+        }
+        Mode::Release => {
+            let values = marginal_values(&players, &composition);
+            print_release_report(&values);
+        }
+        Mode::AllFormations => {
+            let results = compare_formations(&players, &formations);
+            print_formation_report(&results, &formations);
+        }
+        Mode::Rotation { matches } => {
+            let plan = plan_rotation(&players, &composition, matches, &availability);
+            print_rotation_report(&plan);
+        }
+        Mode::Simulate { runs, noise, absence, seed } => {
+            let pick_data: Vec<_> = players.iter().map(|p| to_pick_data(p, &composition)).collect();
+            let baseline: Vec<String> = pick_lineup(&pick_data, &composition)
+                .map(|(lineup, _)| lineup.into_iter().map(|s| s.name).collect())
+                .unwrap_or_default();
 
-    let (optimized_team, _) = optimize_lineup(&all_pick_data, initial, &composition);
+            let settings = SimulationSettings { runs, noise, absence, seed };
+            let result = simulate(&players, &composition, &settings);
+            print_simulation_report(&result, &baseline);
+        }
+        Mode::Sweep { constant, from, to, step } => {
+            match sweep_constant(&players, &composition, &constant, &sweep_values(from, to, step)) {
+                Ok(points) => {
+                    let current = composition.constants.iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case(&constant))
+                        .map(|(_, v)| *v)
+                        .unwrap_or_default();
+                    print_sweep_report(&constant, current, &points);
+                }
+                Err(msg) => println!("{}", msg),
+            }
+        }
+        Mode::Fit { labels_file } => {
+            let data = read_fit_labels(&labels_file)?;
+            match fit_coefficients(&players, &composition, &data) {
+                Ok(result) => {
+                    print_fit_report(&data, &result);
 
-    
-    let mut sorted_team = optimized_team;
-    sorted_team.sort_by_key(|p| {
-        let pos = &p.offense.position;
-        let sort_key = match pos.as_str() {
-            "RN" => 0,
-            "GN" => 1,
-            "BK" => 2,
-            _ => 3,
-        };
-        // Pack into a tuple: first by role, then descending total_score
-        (sort_key, -((p.total_score * 100.0) as i32))
-    });
-    
-    // Find longest name. We'll use this to space things correctly.
-    let longest_name = sorted_team.iter().map(|p| p.name.len()).max().unwrap_or(0);
-    let padding = 3;
-    
-    // Headers
-    println!("{:<10}{:<name_width$}{}", "Pos", "Name", "Score", name_width = longest_name + padding);
-    
-    let mut total_off = 0;
-    let mut total_def = 0;
-    
-    for player in &sorted_team {
-        let off_val = player.offense.score.round() as i32;
-        let def_val = player.defense.score.round() as i32;
-        let total = off_val + def_val;
-        total_off += off_val;
-        total_def += def_val;
-    
-        println!(
-            "{} / {}   {:<name_width$}{:>2.0} + {:>2.0} = {:>3.0}",
-            player.offense.position,
-            player.defense.position,
-            player.name,
-            off_val,
-            def_val,
-            total,
-            name_width = longest_name + padding
-        );
+                    // Every formation shares the fitted formula
+                    let formula = fitted_formula(&data.template, &result.coefficients);
+                    let fitted: Vec<_> = formations.into_iter()
+                        .map(|(name, mut reqs)| {
+                            reqs.position_to_calculation.insert(data.template.position.clone(), formula.clone());
+                            (name, reqs)
+                        })
+                        .collect();
+
+                    match config.output_file.as_deref() {
+                        Some(path) => {
+                            write_report(Some(path), &formations_to_json(&fitted).to_pretty_string())?;
+                        }
+                        None => println!("\nUse -o to save the composition with the fitted formula."),
+                    }
+                }
+                Err(msg) => println!("{}", msg),
+            }
+        }
+        Mode::League { .. } | Mode::ExportJson | Mode::ConvertComposition
+            | Mode::Snapshot | Mode::Deltas { .. } | Mode::Progress => {
+            unreachable!("handled before the lineup is needed")
+        }
+        Mode::Compare { opponent_file } => {
+            let opponents = read_team(&opponent_file, config.roster_format)?;
+
+            let our_data: Vec<_> = players.iter().map(|p| to_pick_data(p, &composition)).collect();
+            let their_data: Vec<_> = opponents.iter().map(|p| to_pick_data(p, &composition)).collect();
+
+            let ours = pick_lineup(&our_data, &composition);
+            let theirs = pick_lineup(&their_data, &composition);
+
+            if let (Some((ours, _)), Some((theirs, _))) = (&ours, &theirs) {
+                let comparison = compare_lineups(
+                    &file_stem(&config.team_file),
+                    ours,
+                    &file_stem(&opponent_file),
+                    theirs,
+                    &composition,
+                );
+                let report = match config.output_format {
+                    OutputFormat::Json => comparison_to_json(&comparison).to_pretty_string(),
+                    _ => format_comparison_text(&comparison),
+                };
+                write_report(config.output_file.as_deref(), &report)?;
+            } else {
+                // Name whichever roster couldn't field a lineup, and why
+                for (file, data, picked) in [
+                    (&config.team_file, &our_data, ours.is_some()),
+                    (&opponent_file, &their_data, theirs.is_some()),
+                ] {
+                    if !picked {
+                        println!("No lineup from {} satisfies the composition:", file);
+                        for reason in explain_infeasible(data, &composition) {
+                            println!("  {}", reason);
+                        }
+                    }
+                }
+            }
+        }
     }
-    
-    println!("\n    Team total: {} + {} = {}", total_off, total_def, total_off + total_def);
+
     pause();
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,105 @@
+// src/objective.rs
+
+use std::fmt;
+
+// How a lineup's slot scores are combined into the number the optimizer
+// maximizes. Scores are weighted (see `Weights:` and `RN*1.5`).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Objective {
+    // Offense total + defense total
+    #[default]
+    Sum,
+    // The weakest slot, ties broken by the sum
+    Maximin,
+    // Sum minus this much times the variance of the slot scores
+    Variance(f64),
+    // This share of the offense total plus the rest of the defense total
+    Mix(f64),
+}
+
+// Small enough never to outweigh a real difference in the weakest slot
+const MAXIMIN_TIE_BREAK: f64 = 1e-6;
+
+impl Objective {
+    // `sum`, `maximin`, `variance <penalty>` or `mix <offense share>`
+    pub fn parse(text: &str) -> Result<Objective, String> {
+        let mut parts = text.split_whitespace();
+        let name = parts.next().unwrap_or("").to_lowercase();
+        let value = parts.next();
+
+        if parts.next().is_some() {
+            return Err(format!("Unexpected text after objective '{}'", text.trim()));
+        }
+
+        let number = |what: &str| -> Result<f64, String> {
+            let value = value.ok_or_else(|| format!("Objective '{}' needs {}", name, what))?;
+            value.parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .ok_or_else(|| format!("Invalid {} '{}' for objective '{}'", what, value, name))
+        };
+
+        let objective = match name.as_str() {
+            "sum" => Objective::Sum,
+            "maximin" => Objective::Maximin,
+            "variance" => Objective::Variance(number("a penalty")?),
+            "mix" => {
+                let share = number("an offense share")?;
+                if share > 1.0 {
+                    return Err(format!("Offense share {} for objective 'mix' must be between 0 and 1", share));
+                }
+                Objective::Mix(share)
+            }
+            _ => return Err(format!(
+                "Unknown objective '{}' (expected sum, maximin, variance or mix)",
+                text.trim()
+            )),
+        };
+
+        if value.is_some() && matches!(objective, Objective::Sum | Objective::Maximin) {
+            return Err(format!("Objective '{}' takes no value", name));
+        }
+
+        Ok(objective)
+    }
+
+    // `offense` and `defense` are the weighted unit totals, `slots` every
+    // weighted slot score in the lineup.
+    pub fn score(&self, offense: f64, defense: f64, slots: &[f64]) -> f64 {
+        match *self {
+            Objective::Sum => offense + defense,
+            Objective::Maximin => {
+                let weakest = slots.iter().copied().reduce(f64::min).unwrap_or(0.0);
+                weakest + (offense + defense) * MAXIMIN_TIE_BREAK
+            }
+            Objective::Variance(penalty) => {
+                let count = slots.len().max(1) as f64;
+                let mean = slots.iter().sum::<f64>() / count;
+                let variance = slots.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / count;
+                offense + defense - penalty * variance
+            }
+            Objective::Mix(share) => share * offense + (1.0 - share) * defense,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            Objective::Sum => "team total".to_string(),
+            Objective::Maximin => "weakest slot".to_string(),
+            Objective::Variance(penalty) => format!("team total - {} x slot variance", penalty),
+            Objective::Mix(share) => format!("{:.0}% offense, {:.0}% defense", share * 100.0, (1.0 - share) * 100.0),
+        }
+    }
+}
+
+// The composition syntax, so it round-trips through `parse`.
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Sum => write!(f, "sum"),
+            Objective::Maximin => write!(f, "maximin"),
+            Objective::Variance(penalty) => write!(f, "variance {}", penalty),
+            Objective::Mix(share) => write!(f, "mix {}", share),
+        }
+    }
+}
@@ -1,15 +1,30 @@
 // src/pick.rs
 
 use std::collections::HashMap;
+use std::sync::Mutex;
 use crate::player::Player;
 use crate::composition::PositionRequirements;
-use crate::evaluator::{evaluate_position};
+use crate::evaluator::{evaluate_in, evaluate_position};
 
 #[derive(Debug, Clone)]
 pub struct PickTempData {
     pub name: String,
     pub position_scores: HashMap<String, f64>,
     pub max_score: f64,
+    pub captain: bool,
+}
+
+// Formula errors already reported, as (position, error). Simulation and the
+// what-if modes build pick data over and over, so each is printed once.
+static REPORTED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+fn report_once(position: &str, error: String, message: impl FnOnce() -> String) {
+    let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (position.to_string(), error);
+    if !reported.contains(&key) {
+        eprintln!("{}", message());
+        reported.push(key);
+    }
 }
 
 pub fn to_pick_data(player: &Player, reqs: &PositionRequirements) -> PickTempData {
@@ -20,13 +35,22 @@ pub fn to_pick_data(player: &Player, reqs: &PositionRequirements) -> PickTempDat
     let mut add_scores = |positions: &[String], max: &mut f64| {
         for pos in positions {
             if !scores.contains_key(pos) {
-                match evaluate_position(player, pos, reqs) {
+                // Ineligible players get no score, so the optimizer never puts them there
+                if !is_eligible(player, pos, reqs) {
+                    continue;
+                }
+
+                // Doubtful players count for what they're expected to contribute
+                match evaluate_position(player, pos, reqs).map(|score| score * player.play_chance) {
                     Ok(score) => {
                         scores.insert(pos.clone(), score);
-                        *max = f64::max(*max, score);
+                        *max = f64::max(*max, score * reqs.weight(pos));
                     }
                     Err(err) => {
-                        eprintln!("Error evaluating {} for {}: {:?}", pos, player.name, err);
+                        report_once(pos, format!("{:?}", err), || format!(
+                            "Error evaluating {} for {}: {:?} (not repeated for other players)",
+                            pos, player.name, err
+                        ));
                         scores.insert(pos.clone(), 0.0);
                     }
                 }
@@ -41,5 +65,23 @@ pub fn to_pick_data(player: &Player, reqs: &PositionRequirements) -> PickTempDat
         name: player.name.clone(),
         position_scores: scores,
         max_score: max_offense + max_defense,
+        captain: player.captain,
+    }
+}
+
+fn is_eligible(player: &Player, position: &str, reqs: &PositionRequirements) -> bool {
+    let Some(rule) = reqs.eligibility.get(position) else {
+        return true;
+    };
+
+    match evaluate_in(player, rule, reqs) {
+        Ok(value) => value != 0.0,
+        Err(err) => {
+            report_once(position, format!("eligibility {:?}", err), || format!(
+                "Error checking eligibility of {} for {}: {:?} (not repeated for other players)",
+                player.name, position, err
+            ));
+            false
+        }
     }
 }
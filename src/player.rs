@@ -5,5 +5,8 @@ use std::collections::HashMap;
 #[derive(Debug, Clone)]
 pub struct Player {
     pub name: String,
-    pub stats: HashMap<String, i32>,
+    pub metadata: String, // e.g. "#7 Common Troll"
+    pub stats: HashMap<String, Option<f64>>, // None for a stat shown as missing, e.g. "-"
+    pub captain: bool, // Shown as "[CAPTAIN] " before the name on the team page
+    pub play_chance: f64, // 1 unless marked doubtful in an availability file
 }
@@ -0,0 +1,116 @@
+// src/release.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::{pick_lineup, StartingPosition};
+use crate::pick::{to_pick_data, PickTempData};
+use crate::player::Player;
+
+#[derive(Debug, Clone)]
+pub struct MarginalValue {
+    pub name: String,
+    pub starter: bool,
+    pub team_drop: Option<f64>,
+    pub bench_drop: f64,
+}
+
+// Removes each player in turn and measures how much the optimized team total
+// and the bench coverage fall. Least valuable players come first.
+pub fn marginal_values(players: &[Player], reqs: &PositionRequirements) -> Vec<MarginalValue> {
+    let pick_data: Vec<PickTempData> = players.iter()
+        .map(|p| to_pick_data(p, reqs))
+        .collect();
+
+    let Some((lineup, baseline)) = pick_lineup(&pick_data, reqs) else {
+        return Vec::new();
+    };
+    let baseline_coverage = bench_coverage(&pick_data, &lineup, reqs);
+
+    let mut values = Vec::new();
+
+    for (i, player) in pick_data.iter().enumerate() {
+        let mut without = pick_data.clone();
+        without.remove(i);
+
+        // Dropping below a full team is reported as having no replacement.
+        let (team_drop, coverage) = match pick_lineup(&without, reqs) {
+            Some((new_lineup, total)) => (
+                Some(baseline - total),
+                bench_coverage(&without, &new_lineup, reqs),
+            ),
+            None => (None, 0.0),
+        };
+
+        values.push(MarginalValue {
+            name: player.name.clone(),
+            starter: lineup.iter().any(|s| s.name == player.name),
+            team_drop,
+            bench_drop: baseline_coverage - coverage,
+        });
+    }
+
+    values.sort_by(|a, b| {
+        let a_key = (a.team_drop.unwrap_or(f64::MAX), a.bench_drop);
+        let b_key = (b.team_drop.unwrap_or(f64::MAX), b.bench_drop);
+        a_key.partial_cmp(&b_key).unwrap()
+    });
+
+    values
+}
+
+// Sum over every position in the composition of the best score a non-starter
+// could provide there. A rough measure of how well injuries can be covered.
+pub fn bench_coverage(
+    pick_data: &[PickTempData],
+    lineup: &[StartingPosition],
+    reqs: &PositionRequirements,
+) -> f64 {
+    let mut positions: Vec<&String> = reqs.attacking.iter().chain(&reqs.defensive).collect();
+    positions.sort();
+    positions.dedup();
+
+    let bench: Vec<&PickTempData> = pick_data.iter()
+        .filter(|p| !lineup.iter().any(|s| s.name == p.name))
+        .collect();
+
+    positions.iter()
+        .map(|pos| {
+            bench.iter()
+                .filter_map(|p| p.position_scores.get(*pos).copied())
+                .fold(0.0, f64::max)
+        })
+        .sum()
+}
+
+pub fn print_release_report(values: &[MarginalValue]) {
+    if values.is_empty() {
+        println!("No players to evaluate.");
+        return;
+    }
+
+    let longest_name = values.iter().map(|v| v.name.len()).max().unwrap_or(0);
+    let padding = 3;
+
+    println!("Marginal value of each player (least valuable first)\n");
+    println!(
+        "{:<name_width$}{:<9}{:>10}{:>12}",
+        "Name", "Role", "Team drop", "Bench drop",
+        name_width = longest_name + padding
+    );
+
+    for value in values {
+        let role = if value.starter { "Starter" } else { "Bench" };
+        let team_drop = match value.team_drop {
+            Some(drop) => format!("{:.1}", drop),
+            None => "no cover".to_string(),
+        };
+
+        println!(
+            "{:<name_width$}{:<9}{:>10}{:>12.1}",
+            value.name,
+            role,
+            team_drop,
+            value.bench_drop,
+            name_width = longest_name + padding
+        );
+    }
+}
@@ -0,0 +1,114 @@
+// src/report.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::{unit_totals, StartingPosition};
+use crate::objective::Objective;
+
+pub fn sort_lineup(team: &[StartingPosition]) -> Vec<StartingPosition> {
+    let mut sorted_team = team.to_vec();
+    sorted_team.sort_by_key(|p| {
+        let pos = &p.offense.position;
+        let sort_key = match pos.as_str() {
+            "RN" => 0,
+            "GN" => 1,
+            "BK" => 2,
+            _ => 3,
+        };
+        // Pack into a tuple: first by role, then descending total_score
+        (sort_key, -((p.total_score * 100.0) as i32))
+    });
+    sorted_team
+}
+
+pub fn print_lineup(team: &[StartingPosition]) {
+    let sorted_team = sort_lineup(team);
+
+    let display_name = |p: &StartingPosition| if p.captain {
+        format!("{} (C)", p.name)
+    } else {
+        p.name.clone()
+    };
+
+    // Find longest name. We'll use this to space things correctly.
+    let longest_name = sorted_team.iter().map(|p| display_name(p).len()).max().unwrap_or(0);
+    let padding = 3;
+
+    // Only show weighted scores when the composition weights something
+    let weighted = sorted_team.iter()
+        .any(|p| p.offense.weight != 1.0 || p.defense.weight != 1.0);
+
+    // Headers
+    if weighted {
+        println!("{:<10}{:<name_width$}{:<18}Weighted", "Pos", "Name", "Score", name_width = longest_name + padding);
+    } else {
+        println!("{:<10}{:<name_width$}Score", "Pos", "Name", name_width = longest_name + padding);
+    }
+
+    let mut total_off = 0;
+    let mut total_def = 0;
+    let mut weighted_off = 0.0;
+    let mut weighted_def = 0.0;
+
+    for player in &sorted_team {
+        let off_val = player.offense.score.round() as i32;
+        let def_val = player.defense.score.round() as i32;
+        let total = off_val + def_val;
+        total_off += off_val;
+        total_def += def_val;
+
+        let line = format!(
+            "{} / {}   {:<name_width$}{:>2.0} + {:>2.0} = {:>3.0}",
+            player.offense.position,
+            player.defense.position,
+            display_name(player),
+            off_val,
+            def_val,
+            total,
+            name_width = longest_name + padding
+        );
+
+        if weighted {
+            let off_weighted = player.offense.weighted_score();
+            let def_weighted = player.defense.weighted_score();
+            weighted_off += off_weighted;
+            weighted_def += def_weighted;
+
+            println!(
+                "{:<line_width$}{:>3.0} + {:>3.0} = {:>3.0}",
+                line,
+                off_weighted,
+                def_weighted,
+                off_weighted + def_weighted,
+                line_width = 10 + longest_name + padding + 18
+            );
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    println!("\n    Team total: {} + {} = {}", total_off, total_def, total_off + total_def);
+    if weighted {
+        println!("Weighted total: {:.0} + {:.0} = {:.0}", weighted_off, weighted_def, weighted_off + weighted_def);
+    }
+}
+
+// Only worth a line when the optimizer maximized something other than the total
+pub fn print_objective(objective: Objective, score: f64) {
+    if objective != Objective::Sum {
+        println!("     Objective: {} ({}) = {:.1}", objective.describe(), objective, score);
+    }
+}
+
+// The optimizer only penalizes missing a unit minimum, so say when no lineup met it
+pub fn print_unit_minimums(team: &[StartingPosition], reqs: &PositionRequirements) {
+    let (offense, defense) = unit_totals(team);
+
+    for (unit, total, minimum) in [
+        ("Offense", offense, reqs.offense_unit.minimum),
+        ("Defense", defense, reqs.defense_unit.minimum),
+    ] {
+        if let Some(minimum) = minimum && total < minimum {
+            println!("Warning: {} total {:.0} is below the minimum of {:.0}", unit, total, minimum);
+        }
+    }
+}
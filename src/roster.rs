@@ -1,14 +1,109 @@
 // src/roster.rs
 
 use std::collections::HashMap;
-use std::io::{BufRead, Result};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::Path;
 
 use crate::file_handling::open_file;
+use crate::json::JsonValue;
 use crate::player::Player;
+use crate::roster_html::parse_html_roster;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RosterFormat {
+    // Name line followed by a tab-separated stat line, as copied from the team page
+    Paste,
+    // One row per player with a header, separated by tabs, commas or semicolons
+    Delimited,
+    // Array of {"name", "metadata", "stats"} objects
+    Json,
+    // Saved copy of the team page
+    Html,
+}
+
+impl RosterFormat {
+    pub fn from_name(name: &str) -> Option<RosterFormat> {
+        match name.to_lowercase().as_str() {
+            "paste" | "txt" => Some(RosterFormat::Paste),
+            "csv" | "tsv" => Some(RosterFormat::Delimited),
+            "json" => Some(RosterFormat::Json),
+            "html" | "htm" => Some(RosterFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &str) -> RosterFormat {
+        let extension = Path::new(path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "" => RosterFormat::Paste,
+            ext => RosterFormat::from_name(ext).unwrap_or(RosterFormat::Paste),
+        }
+    }
+}
+
+// Reads a roster, picking the format from the file extension.
 pub fn read_roster(path: &str) -> Result<Vec<Player>> {
-    let reader = open_file(path)?;
-    let mut lines = reader.lines().filter_map(Result::ok);
+    read_roster_as(path, RosterFormat::from_path(path))
+}
+
+pub fn read_roster_as(path: &str, format: RosterFormat) -> Result<Vec<Player>> {
+    let mut text = String::new();
+    open_file(path)?.read_to_string(&mut text)?;
+
+    match format {
+        RosterFormat::Paste => parse_paste(&text),
+        RosterFormat::Delimited => parse_delimited(&text),
+        RosterFormat::Json => parse_json_roster(&text),
+        RosterFormat::Html => parse_html_roster(&text),
+    }
+}
+
+pub fn parse_paste(text: &str) -> Result<Vec<Player>> {
+    let (players, notes) = parse_paste_with_notes(text)?;
+    for note in notes {
+        eprintln!("Note: {}", note);
+    }
+    Ok(players)
+}
+
+// Things the paste parser had to work around, reported once per file.
+#[derive(Debug, Default)]
+struct PasteRecovery {
+    windows_line_endings: bool,
+    space_separated: usize,
+    blank_lines: usize,
+}
+
+impl PasteRecovery {
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.windows_line_endings {
+            notes.push("converted Windows line endings".to_string());
+        }
+        if self.space_separated > 0 {
+            notes.push(format!(
+                "read {} stat line(s) separated by spaces instead of tabs, taking the last columns as stats",
+                self.space_separated
+            ));
+        }
+        if self.blank_lines > 0 {
+            notes.push(format!("skipped {} blank line(s) between a name and its stats", self.blank_lines));
+        }
+        notes
+    }
+}
+
+pub fn parse_paste_with_notes(text: &str) -> Result<(Vec<Player>, Vec<String>)> {
+    let mut recovery = PasteRecovery {
+        windows_line_endings: text.contains('\r'),
+        ..Default::default()
+    };
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines = text.lines();
 
     let mut players = Vec::new();
 
@@ -17,7 +112,7 @@ pub fn read_roster(path: &str) -> Result<Vec<Player>> {
         match lines.next() {
             Some(line) if !line.trim().is_empty() => break line,
             Some(_) => continue,
-            None => return Ok(players), // empty or malformed file
+            None => return Ok((players, Vec::new())), // empty or malformed file
         }
     };
 
@@ -27,54 +122,293 @@ pub fn read_roster(path: &str) -> Result<Vec<Player>> {
         .collect();
 
     if header_fields.first().map(|s| s.as_str()) != Some("Name") {
-        panic!("First header column must be 'Name'");
+        return Err(Error::new(ErrorKind::InvalidData, "First header column must be 'Name'"));
     }
     header_fields.remove(0); // Remove "Name" column
 
-    // Read player entries (two lines per player: name, stat line)
-    while let Some(name_line) = lines.next() {
-        let name = name_line.trim();
+    // Read player entries (a name line, then a stat line)
+    let mut pending_name: Option<(String, bool)> = None;
+    let mut blank_since_name = false;
 
-        if name.is_empty() {
+    for line in lines {
+        let line = line.trim();
+
+        if line.is_empty() {
+            blank_since_name |= pending_name.is_some();
             continue;
         }
 
-        let name = name.strip_prefix("[CAPTAIN] ").unwrap_or(name).to_string();
+        let Some((name, captain)) = pending_name.take() else {
+            pending_name = Some(split_captain(line));
+            blank_since_name = false;
+            continue;
+        };
 
-        let stat_line = match lines.next() {
-            Some(line) => line.trim().to_string(),
-            None => break, // end of file
+        let (metadata, stat_tokens) = match split_stat_line(line, header_fields.len()) {
+            StatLine::Tabbed(metadata, stat_tokens) => (metadata, stat_tokens),
+            StatLine::Spaced(metadata, stat_tokens) => {
+                recovery.space_separated += 1;
+                (metadata, stat_tokens)
+            }
+            StatLine::Mismatch => {
+                eprintln!("Warning: stat count mismatch for player '{}'", name);
+                continue;
+            }
+            StatLine::NotStats => {
+                // Looks like the next player's name, so this one had no stats
+                eprintln!("Warning: malformed stat line for player '{}'", name);
+                pending_name = Some(split_captain(line));
+                blank_since_name = false;
+                continue;
+            }
         };
 
-        if stat_line.is_empty() {
-            continue;
+        if blank_since_name {
+            recovery.blank_lines += 1;
         }
 
-        let parts: Vec<&str> = stat_line.split('\t').collect();
-        if parts.len() <= 1 {
-            eprintln!("Warning: malformed stat line for player '{}'", name);
-            continue;
+        let stats = parse_stats(&name, header_fields.iter().zip(stat_tokens));
+        players.push(Player { name, metadata, stats, captain, play_chance: 1.0 });
+    }
+
+    if let Some((name, _)) = pending_name {
+        eprintln!("Warning: no stat line for player '{}'", name);
+    }
+
+    Ok((players, recovery.notes()))
+}
+
+enum StatLine<'a> {
+    // Metadata and stat tokens, split on tabs as copied from the site
+    Tabbed(String, Vec<&'a str>),
+    // Same, recovered from a line whose tabs became spaces
+    Spaced(String, Vec<&'a str>),
+    // Looks like stats, but the wrong number of them
+    Mismatch,
+    NotStats,
+}
+
+fn split_stat_line(line: &str, stat_count: usize) -> StatLine<'_> {
+    let parts: Vec<&str> = line.split('\t').collect();
+    if parts.len() == stat_count + 1 {
+        return StatLine::Tabbed(parts[0].trim().to_string(), parts[1..].to_vec());
+    }
+
+    // Tabs lost or mixed with spaces: the stats are the trailing numeric
+    // tokens and everything before them is metadata (race names can have spaces).
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() >= stat_count {
+        let (metadata, stats) = tokens.split_at(tokens.len() - stat_count);
+        if stats.iter().all(|t| is_stat_token(t)) && !metadata.last().is_some_and(|t| is_stat_token(t)) {
+            return StatLine::Spaced(metadata.join(" "), stats.to_vec());
         }
+    }
+
+    let numeric = tokens.iter().filter(|t| is_stat_token(t)).count();
+    if parts.len() > 1 || numeric * 2 >= stat_count.max(1) {
+        StatLine::Mismatch
+    } else {
+        StatLine::NotStats
+    }
+}
+
+fn is_stat_token(token: &str) -> bool {
+    parse_stat_value(token).is_some()
+}
+
+pub fn parse_delimited(text: &str) -> Result<Vec<Player>> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
 
-        let stat_tokens = &parts[1..]; // Skip metadata (first field)
+    let Some(header_line) = lines.next() else {
+        return Ok(Vec::new());
+    };
+
+    let delimiter = detect_delimiter(header_line);
+    let header_fields = split_delimited(header_line, delimiter);
+
+    let Some(name_index) = header_fields.iter().position(|f| f.eq_ignore_ascii_case("Name")) else {
+        return Err(Error::new(ErrorKind::InvalidData, "Header must have a 'Name' column"));
+    };
+    let metadata_index = header_fields.iter().position(|f| f.eq_ignore_ascii_case("Metadata"));
 
-        if stat_tokens.len() != header_fields.len() {
+    let mut players = Vec::new();
+
+    for line in lines {
+        let fields = split_delimited(line, delimiter);
+
+        let (name, captain) = split_captain(fields.get(name_index).map(|s| s.trim()).unwrap_or(""));
+        if name.is_empty() {
+            eprintln!("Warning: row without a name: '{}'", line);
+            continue;
+        }
+
+        if fields.len() != header_fields.len() {
             eprintln!("Warning: stat count mismatch for player '{}'", name);
             continue;
         }
 
+        let metadata = metadata_index
+            .map(|i| fields[i].clone())
+            .unwrap_or_default();
+
+        let stat_fields = header_fields.iter()
+            .zip(fields.iter().map(|s| s.as_str()))
+            .enumerate()
+            .filter(|(i, _)| *i != name_index && Some(*i) != metadata_index)
+            .map(|(_, pair)| pair);
+
+        let stats = parse_stats(&name, stat_fields);
+        players.push(Player { name, metadata, stats, captain, play_chance: 1.0 });
+    }
+
+    Ok(players)
+}
+
+pub fn parse_json_roster(text: &str) -> Result<Vec<Player>> {
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidData, msg);
+
+    let root = JsonValue::parse(text).map_err(invalid)?;
+    let JsonValue::Array(entries) = root else {
+        return Err(invalid("Roster JSON must be an array of players".to_string()));
+    };
+
+    let mut players = Vec::new();
+
+    for entry in &entries {
+        let Some(name) = entry.get("name").and_then(JsonValue::as_str) else {
+            eprintln!("Warning: skipping roster entry without a name");
+            continue;
+        };
+        let (name, prefixed) = split_captain(name);
+        let captain = prefixed || matches!(entry.get("captain"), Some(JsonValue::Bool(true)));
+
+        let metadata = entry.get("metadata")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string();
+
         let mut stats = HashMap::new();
-        for (field, value_str) in header_fields.iter().zip(stat_tokens.iter()) {
-            if let Ok(value) = value_str.trim().parse::<i32>() {
-                stats.insert(field.clone(), value);
-            } else {
-                eprintln!("Warning: invalid number '{}' for player '{}'", value_str, name);
-                continue;
+        if let Some(JsonValue::Object(fields)) = entry.get("stats") {
+            for (field, value) in fields {
+                match value {
+                    JsonValue::Number(v) => {
+                        stats.insert(field.clone(), Some(*v));
+                    }
+                    JsonValue::Null => {
+                        stats.insert(field.clone(), None);
+                    }
+                    _ => eprintln!("Warning: invalid number for '{}' of player '{}'", field, name),
+                }
             }
         }
 
-        players.push(Player { name, stats });
+        players.push(Player { name, metadata, stats, captain, play_chance: 1.0 });
     }
 
     Ok(players)
 }
+
+pub fn roster_to_json(players: &[Player]) -> JsonValue {
+    JsonValue::Array(players.iter().map(|player| {
+        let mut stats: Vec<(&String, &Option<f64>)> = player.stats.iter().collect();
+        stats.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut fields = vec![
+            ("name", JsonValue::string(&player.name)),
+            ("metadata", JsonValue::string(&player.metadata)),
+        ];
+        if player.captain {
+            fields.push(("captain", JsonValue::Bool(true)));
+        }
+        fields.push(("stats", JsonValue::Object(stats.into_iter()
+            .map(|(k, v)| (k.clone(), v.map(JsonValue::Number).unwrap_or(JsonValue::Null)))
+            .collect())));
+
+        JsonValue::object(fields)
+    }).collect())
+}
+
+pub fn parse_stats<'a>(
+    name: &str,
+    fields: impl Iterator<Item = (&'a String, &'a str)>,
+) -> HashMap<String, Option<f64>> {
+    let mut stats = HashMap::new();
+    for (field, value_str) in fields {
+        let value = parse_stat_value(value_str);
+        if value.is_none() {
+            eprintln!("Warning: invalid number '{}' for player '{}', treating it as missing", value_str, name);
+        }
+        stats.insert(field.clone(), value.flatten());
+    }
+    stats
+}
+
+// Some(Some(value)) for a number, Some(None) for a missing-value placeholder
+// and None for anything else. Accepts decimal commas ("12,5") with one or two
+// digits after the comma; "1,234" looks like a thousands separator, so it's
+// rejected rather than read as 1.234.
+pub fn parse_stat_value(token: &str) -> Option<Option<f64>> {
+    let token = token.trim();
+    if matches!(token, "" | "-" | "–" | "—" | "?") || token.eq_ignore_ascii_case("n/a") {
+        return Some(None);
+    }
+
+    let parsed = match token.parse::<f64>() {
+        Ok(value) => Ok(value),
+        Err(_) => match token.split_once(',') {
+            Some((whole, fraction))
+                if !whole.contains('.')
+                    && (1..=2).contains(&fraction.len())
+                    && fraction.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                format!("{}.{}", whole, fraction).parse::<f64>()
+            }
+            _ => return None,
+        },
+    };
+
+    parsed.ok().filter(|v| v.is_finite()).map(Some)
+}
+
+// Removes the "[CAPTAIN] " prefix; the flag says whether it was there.
+pub fn split_captain(name: &str) -> (String, bool) {
+    match name.strip_prefix("[CAPTAIN] ") {
+        Some(name) => (name.to_string(), true),
+        None => (name.to_string(), false),
+    }
+}
+
+// Whichever of tab, comma or semicolon appears most in the header.
+// Ties go to tab, which comes last.
+fn detect_delimiter(header: &str) -> char {
+    [';', ',', '\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap()
+}
+
+// Splits one row, honouring double-quoted fields.
+fn split_delimited(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+
+    fields
+}
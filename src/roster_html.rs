@@ -0,0 +1,291 @@
+// src/roster_html.rs
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::player::Player;
+use crate::roster::{parse_stats, split_captain};
+
+#[derive(Debug, Default)]
+struct Cell {
+    text: String,
+    captain: bool,
+}
+
+type Table = Vec<Vec<Cell>>;
+
+// Reads the player table from a saved team page. The table is the first one
+// with a "Name" header cell. The name cell holds the player's name on its first
+// line and the metadata (number, race) on the following lines, which is what
+// the paste format sees as "name line" and "first field of the stat line".
+pub fn parse_html_roster(html: &str) -> Result<Vec<Player>> {
+    let tables = extract_tables(html);
+
+    let found = tables.iter().find_map(|table| {
+        table.iter()
+            .position(|row| row.iter().any(|cell| is_name_header(&cell.text)))
+            .map(|header_index| (table, header_index))
+    });
+
+    let Some((table, header_index)) = found else {
+        return Err(Error::new(ErrorKind::InvalidData, "No player table with a 'Name' column found"));
+    };
+
+    let header: Vec<String> = table[header_index].iter()
+        .map(|cell| single_line(&cell.text))
+        .collect();
+    let name_index = header.iter().position(|h| is_name_header(h)).unwrap();
+
+    let mut players = Vec::new();
+
+    for row in &table[header_index + 1..] {
+        // Repeated header rows, spacer rows and footers
+        if row.len() != header.len() || is_name_header(&row[name_index].text) {
+            continue;
+        }
+
+        let name_cell = &row[name_index];
+        let mut lines = name_cell.text.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        let Some(name) = lines.next() else {
+            continue;
+        };
+        // A captain badge element was already left out of the name text
+        let (name, prefixed) = split_captain(name);
+        let captain = prefixed || name_cell.captain;
+
+        let mut metadata: Vec<String> = lines.map(|l| l.to_string()).collect();
+        let mut stat_fields = Vec::new();
+
+        for (i, (field, cell)) in header.iter().zip(row).enumerate() {
+            if i == name_index {
+                continue;
+            }
+            let value = single_line(&cell.text);
+            if field.is_empty() || field.eq_ignore_ascii_case("Metadata") {
+                if !value.is_empty() {
+                    metadata.push(value);
+                }
+            } else {
+                stat_fields.push((field, value));
+            }
+        }
+
+        let stats = parse_stats(&name, stat_fields.iter().map(|(f, v)| (*f, v.as_str())));
+        players.push(Player { name, metadata: metadata.join(" "), stats, captain, play_chance: 1.0 });
+    }
+
+    Ok(players)
+}
+
+fn is_name_header(text: &str) -> bool {
+    single_line(text).eq_ignore_ascii_case("Name")
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Collects every table as rows of cells. Text inside an element marked as a
+// captain badge is dropped and the cell is flagged instead.
+fn extract_tables(html: &str) -> Vec<Table> {
+    let mut tables: Vec<Table> = Vec::new();
+    let mut open_tables: Vec<Table> = Vec::new();
+    let mut cell: Option<Cell> = None;
+    let mut captain_tag: Option<String> = None;
+
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            if let Some(cell) = cell.as_mut()
+                && captain_tag.is_none()
+            {
+                push_text(&mut cell.text, &decode_entities(&rest[..end]));
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name: String = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '!')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let attributes = &tag[name.len().min(tag.len())..];
+
+        // Skip anything that isn't page content
+        if !closing && (name == "script" || name == "style") {
+            let close = format!("</{}", name);
+            rest = find_ignore_case(rest, &close).map(|i| &rest[i..]).unwrap_or("");
+            continue;
+        }
+
+        if let Some(badge) = &captain_tag {
+            if closing && name == *badge {
+                captain_tag = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("table", false) => open_tables.push(Vec::new()),
+            ("table", true) => {
+                if let Some(table) = open_tables.pop() {
+                    tables.push(table);
+                }
+            }
+            ("tr", false) => {
+                if let Some(table) = open_tables.last_mut() {
+                    table.push(Vec::new());
+                }
+            }
+            ("td" | "th", false) => cell = Some(Cell::default()),
+            ("td" | "th", true) => {
+                if let (Some(finished), Some(row)) = (
+                    cell.take(),
+                    open_tables.last_mut().and_then(|t| t.last_mut()),
+                ) {
+                    row.push(finished);
+                }
+            }
+            ("br" | "div" | "p" | "li", _) => {
+                if let Some(cell) = cell.as_mut() {
+                    cell.text.push('\n');
+                }
+            }
+            (_, false) if is_captain_badge(attributes) => {
+                if let Some(cell) = cell.as_mut() {
+                    cell.captain = true;
+                    let void = tag.ends_with('/') || matches!(name.as_str(), "img" | "input");
+                    if !void {
+                        captain_tag = Some(name);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Unclosed tables at the end of a truncated page
+    tables.extend(open_tables);
+    tables
+}
+
+// A badge has a `captain` class, or one such as `captain-badge`. Other
+// mentions, like `vice-captain` or a title, don't count.
+fn is_captain_badge(attributes: &str) -> bool {
+    attribute_value(attributes, "class").is_some_and(|classes| {
+        classes.split_whitespace().any(|class| {
+            let class = class.to_ascii_lowercase();
+            class == "captain" || class.starts_with("captain-")
+        })
+    })
+}
+
+// The value of attribute `name` in a tag's attributes, quoted or not.
+fn attribute_value<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+
+        let key_end = rest.find(|c: char| c.is_whitespace() || c == '=' || c == '/').unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let inner = &after[1..];
+                        let end = inner.find(quote).unwrap_or(inner.len());
+                        rest = inner.get(end + 1..).unwrap_or("");
+                        &inner[..end]
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        rest = &after[end..];
+                        &after[..end]
+                    }
+                }
+            }
+            None => "",
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+    }
+}
+
+// Source line breaks are just formatting; only <br> and block ends split lines.
+fn push_text(target: &mut String, text: &str) {
+    target.extend(text.chars().map(|c| if c.is_whitespace() { ' ' } else { c }));
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.to_ascii_lowercase().find(&needle.to_ascii_lowercase())
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+            }
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
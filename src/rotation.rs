@@ -0,0 +1,209 @@
+// src/rotation.rs
+
+use crate::availability::Availability;
+use crate::composition::PositionRequirements;
+use crate::evaluator::evaluate_in;
+use crate::lineup::{explain_infeasible, pick_lineup, unit_totals, StartingPosition};
+use crate::pick::{to_pick_data, PickTempData};
+use crate::player::Player;
+
+// Matches in a row a player may start when the composition has no stamina formula
+const DEFAULT_MAX_CONSECUTIVE: usize = 2;
+
+pub struct MatchPlan {
+    pub lineup: Vec<StartingPosition>,
+    pub score: f64,
+    // Players who had to start although they were due a rest, because there
+    // weren't enough rested players to fill the slots
+    pub overworked: Vec<String>,
+}
+
+pub struct RotationPlan {
+    // Max consecutive starts per player, in roster order
+    pub limits: Vec<(String, usize)>,
+    pub matches: Vec<MatchPlan>,
+    // Why the match after the last planned one couldn't be filled, if
+    // planning stopped early
+    pub unfilled: Vec<String>,
+}
+
+// The stamina formula, rounded down; every player can start at least once.
+pub fn max_consecutive_starts(player: &Player, reqs: &PositionRequirements) -> usize {
+    let Some(formula) = &reqs.stamina else {
+        return DEFAULT_MAX_CONSECUTIVE;
+    };
+
+    match evaluate_in(player, formula, reqs) {
+        Ok(value) if value.is_finite() => value.floor().max(1.0) as usize,
+        Ok(_) => DEFAULT_MAX_CONSECUTIVE,
+        Err(err) => {
+            eprintln!("Error evaluating stamina for {}: {:?}", player.name, err);
+            DEFAULT_MAX_CONSECUTIVE
+        }
+    }
+}
+
+// Plans the matches greedily, then tries resting each starter in each match
+// and keeps any change that raises the summed lineup score (as maximized by
+// the optimizer), until nothing helps.
+pub fn plan_rotation(
+    players: &[Player],
+    reqs: &PositionRequirements,
+    matches: usize,
+    availability: &Availability,
+) -> RotationPlan {
+    let pick_data: Vec<PickTempData> = players.iter().map(|p| to_pick_data(p, reqs)).collect();
+    let missed: Vec<usize> = players.iter().map(|p| availability.matches_missed(&p.name)).collect();
+    let limits: Vec<usize> = players.iter().map(|p| max_consecutive_starts(p, reqs)).collect();
+
+    let mut rests: Vec<(usize, String)> = Vec::new();
+    let mut best = plan_matches(&pick_data, &limits, &missed, reqs, matches, &rests);
+
+    'search: loop {
+        for (index, planned) in best.iter().enumerate() {
+            for starter in &planned.lineup {
+                let mut trial_rests = rests.clone();
+                trial_rests.push((index, starter.name.clone()));
+
+                let trial = plan_matches(&pick_data, &limits, &missed, reqs, matches, &trial_rests);
+                if trial.len() == best.len() && summed_score(&trial) > summed_score(&best) {
+                    rests = trial_rests;
+                    best = trial;
+                    continue 'search;
+                }
+            }
+        }
+        break;
+    }
+
+    let unfilled = if best.len() < matches {
+        let index = best.len();
+        let fit: Vec<PickTempData> = (0..pick_data.len())
+            .filter(|&i| missed[i] <= index)
+            .map(|i| pick_data[i].clone())
+            .collect();
+        explain_infeasible(&fit, reqs)
+    } else {
+        Vec::new()
+    };
+
+    RotationPlan {
+        limits: players.iter().map(|p| p.name.clone()).zip(limits).collect(),
+        matches: best,
+        unfilled,
+    }
+}
+
+fn summed_score(plans: &[MatchPlan]) -> f64 {
+    plans.iter().map(|p| p.score).sum()
+}
+
+// Picks the best lineup for each match in turn from the players who haven't
+// reached their consecutive-start limit and aren't in `rests` (match index,
+// name) for that match. Anyone who sits out a match is fully rested for the
+// next one. `missed` is how many matches each player is out for.
+fn plan_matches(
+    pick_data: &[PickTempData],
+    limits: &[usize],
+    missed: &[usize],
+    reqs: &PositionRequirements,
+    matches: usize,
+    rests: &[(usize, String)],
+) -> Vec<MatchPlan> {
+    let mut streaks = vec![0; pick_data.len()];
+    let mut plans = Vec::new();
+
+    for index in 0..matches {
+        let fit: Vec<usize> = (0..pick_data.len()).filter(|&i| missed[i] <= index).collect();
+
+        let rested: Vec<PickTempData> = fit.iter()
+            .filter(|&&i| streaks[i] < limits[i])
+            .filter(|&&i| !rests.iter().any(|(m, name)| *m == index && *name == pick_data[i].name))
+            .map(|&i| pick_data[i].clone())
+            .collect();
+
+        let (lineup, score, overworked) = match pick_lineup(&rested, reqs) {
+            Some((lineup, score)) => (lineup, score, Vec::new()),
+            None => {
+                // Not enough rested players: the tired ones have to play too
+                let fit_data: Vec<PickTempData> = fit.iter().map(|&i| pick_data[i].clone()).collect();
+                let Some((lineup, score)) = pick_lineup(&fit_data, reqs) else {
+                    break;
+                };
+                let overworked = lineup.iter()
+                    .filter(|s| !rested.iter().any(|p| p.name == s.name))
+                    .map(|s| s.name.clone())
+                    .collect();
+                (lineup, score, overworked)
+            }
+        };
+
+        for (player, streak) in pick_data.iter().zip(streaks.iter_mut()) {
+            if lineup.iter().any(|s| s.name == player.name) {
+                *streak += 1;
+            } else {
+                *streak = 0;
+            }
+        }
+
+        plans.push(MatchPlan { lineup, score, overworked });
+    }
+
+    plans
+}
+
+pub fn print_rotation_report(plan: &RotationPlan) {
+    if plan.matches.is_empty() {
+        print_unfilled(plan);
+        return;
+    }
+
+    println!("{:<7}{:>6}{:>6}{:>7}", "Match", "Off", "Def", "Total");
+
+    let mut summed = 0.0;
+    for (i, result) in plan.matches.iter().enumerate() {
+        let (offense, defense) = unit_totals(&result.lineup);
+        summed += offense + defense;
+        println!("{:<7}{:>6.0}{:>6.0}{:>7.0}", i + 1, offense, defense, offense + defense);
+    }
+    println!("\nSummed total: {:.0}\n", summed);
+
+    let name_width = plan.limits.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 3;
+
+    print!("{:<name_width$}{:>4}", "Player", "Max");
+    for i in 0..plan.matches.len() {
+        print!("{:>8}", i + 1);
+    }
+    println!();
+
+    for (name, limit) in &plan.limits {
+        print!("{:<name_width$}{:>4}", name, limit);
+        for result in &plan.matches {
+            let cell = match result.lineup.iter().find(|s| s.name == *name) {
+                Some(s) => {
+                    let mark = if result.overworked.contains(name) { "!" } else { "" };
+                    format!("{}/{}{}", s.offense.position, s.defense.position, mark)
+                }
+                None => "-".to_string(),
+            };
+            print!("{:>8}", cell);
+        }
+        println!();
+    }
+
+    if plan.matches.iter().any(|m| !m.overworked.is_empty()) {
+        println!("\n! Started without rest: not enough rested players for that match");
+    }
+
+    if !plan.unfilled.is_empty() {
+        println!();
+        print_unfilled(plan);
+    }
+}
+
+fn print_unfilled(plan: &RotationPlan) {
+    println!("No lineup for match {}:", plan.matches.len() + 1);
+    for reason in &plan.unfilled {
+        println!("  {}", reason);
+    }
+}
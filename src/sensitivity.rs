@@ -0,0 +1,129 @@
+// src/sensitivity.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::{pick_lineup, unit_totals};
+use crate::pick::to_pick_data;
+use crate::player::Player;
+
+// Intervals used when no step is given
+const DEFAULT_STEPS: usize = 20;
+
+pub struct SweepPoint {
+    pub value: f64,
+    // (name, offense position, defense position), sorted by name; empty if
+    // no lineup could be picked
+    pub starters: Vec<(String, String, String)>,
+    pub total: Option<f64>,
+}
+
+// `from` to `to` inclusive, in steps of `step` (or DEFAULT_STEPS equal steps).
+pub fn sweep_values(from: f64, to: f64, step: Option<f64>) -> Vec<f64> {
+    let step = step.unwrap_or((to - from).abs() / DEFAULT_STEPS as f64);
+    if step <= 0.0 || from == to {
+        return vec![from];
+    }
+
+    let direction = if to >= from { 1.0 } else { -1.0 };
+    let count = ((to - from).abs() / step + 1e-9).floor() as usize;
+
+    // Rounded so steps like 0.1 don't print as 1.2000000000000002
+    (0..=count)
+        .map(|i| ((from + direction * step * i as f64) * 1e9).round() / 1e9)
+        .collect()
+}
+
+// Re-picks the lineup with the composition constant `name` set to each value.
+pub fn sweep_constant(
+    players: &[Player],
+    reqs: &PositionRequirements,
+    name: &str,
+    values: &[f64],
+) -> Result<Vec<SweepPoint>, String> {
+    let Some(key) = reqs.constants.keys().find(|k| k.eq_ignore_ascii_case(name)).cloned() else {
+        let mut defined: Vec<&String> = reqs.constants.keys().collect();
+        defined.sort();
+        let defined: Vec<&str> = defined.into_iter().map(|s| s.as_str()).collect();
+        return Err(format!(
+            "Constant '{}' isn't defined in the composition (defined: {})",
+            name,
+            if defined.is_empty() { "none".to_string() } else { defined.join(", ") }
+        ));
+    };
+
+    let mut trial_reqs = reqs.clone();
+
+    Ok(values.iter().map(|&value| {
+        trial_reqs.constants.insert(key.clone(), value);
+
+        let pick_data: Vec<_> = players.iter().map(|p| to_pick_data(p, &trial_reqs)).collect();
+
+        match pick_lineup(&pick_data, &trial_reqs) {
+            Some((lineup, _)) => {
+                let (offense, defense) = unit_totals(&lineup);
+                let mut starters: Vec<(String, String, String)> = lineup.into_iter()
+                    .map(|s| (s.name, s.offense.position, s.defense.position))
+                    .collect();
+                starters.sort();
+                SweepPoint { value, starters, total: Some(offense + defense) }
+            }
+            None => SweepPoint { value, starters: Vec::new(), total: None },
+        }
+    }).collect())
+}
+
+// How the lineup differs from the previous point: players in and out, or
+// just position changes. Empty if nothing changed.
+fn describe_change(previous: &SweepPoint, current: &SweepPoint) -> String {
+    if previous.starters == current.starters {
+        return String::new();
+    }
+    if current.total.is_none() {
+        return "no lineup".to_string();
+    }
+
+    let names = |point: &SweepPoint| -> Vec<String> {
+        point.starters.iter().map(|(name, _, _)| name.clone()).collect()
+    };
+    let (before, after) = (names(previous), names(current));
+
+    let mut changes: Vec<String> = after.iter()
+        .filter(|name| !before.contains(name))
+        .map(|name| format!("+{}", name))
+        .collect();
+    changes.extend(before.iter()
+        .filter(|name| !after.contains(name))
+        .map(|name| format!("-{}", name)));
+
+    if changes.is_empty() {
+        "positions changed".to_string()
+    } else {
+        changes.join(", ")
+    }
+}
+
+pub fn print_sweep_report(name: &str, current: f64, points: &[SweepPoint]) {
+    println!("{} (currently {})\n", name, current);
+    println!("{:<10}{:>7}   Lineup change", "Value", "Total");
+
+    let mut change_values = Vec::new();
+
+    for (i, point) in points.iter().enumerate() {
+        let change = match i {
+            0 => String::new(),
+            _ => describe_change(&points[i - 1], point),
+        };
+        if !change.is_empty() {
+            change_values.push(format!("{}", point.value));
+        }
+
+        let total = point.total.map(|t| format!("{:.0}", t)).unwrap_or_else(|| "-".to_string());
+        let marker = if point.value == current { "*" } else { " " };
+        println!("{:<9}{}{:>7}   {}", format!("{}", point.value), marker, total, change);
+    }
+
+    if change_values.is_empty() {
+        println!("\nThe lineup is the same across the whole range.");
+    } else {
+        println!("\nThe lineup changes at: {}", change_values.join(", "));
+    }
+}
@@ -0,0 +1,165 @@
+// src/simulation.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::{pick_lineup, unit_totals};
+use crate::pick::to_pick_data;
+use crate::player::Player;
+
+pub struct SimulationSettings {
+    pub runs: usize,
+    // Standard deviation of each stat, as a fraction of its value
+    pub noise: f64,
+    // Chance each player misses a run, on top of doubtful players' chance
+    pub absence: f64,
+    pub seed: u64,
+}
+
+pub struct SimulationResult {
+    // Per player: how many runs they started, in roster order
+    pub starts: Vec<(String, usize)>,
+    // Raw team total of every run that could field a lineup
+    pub totals: Vec<f64>,
+    // Runs without enough players to fill the slots
+    pub failed_runs: usize,
+    pub runs: usize,
+}
+
+// Small, seedable PRNG (xorshift64*) so runs are repeatable without a crate.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero; scramble the seed so small seeds differ a lot
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ 0xD1B5_4A32_D192_ED03;
+        if state == 0 {
+            state = 1;
+        }
+        Self { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Standard normal, by Box-Muller.
+    pub fn next_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64(); // (0, 1], so ln is finite
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+// Re-picks the lineup `settings.runs` times with every stat perturbed by
+// relative Gaussian noise and players randomly missing. A doubtful player's
+// `play_chance` is used as the chance they turn up rather than to scale scores.
+pub fn simulate(players: &[Player], reqs: &PositionRequirements, settings: &SimulationSettings) -> SimulationResult {
+    let mut rng = Rng::new(settings.seed);
+    let mut starts = vec![0; players.len()];
+    let mut totals = Vec::with_capacity(settings.runs);
+    let mut failed_runs = 0;
+
+    for _ in 0..settings.runs {
+        let mut sampled = Vec::new();
+        let mut roster_index = Vec::new();
+
+        for (i, player) in players.iter().enumerate() {
+            let plays = player.play_chance * (1.0 - settings.absence);
+            if rng.next_f64() >= plays {
+                continue;
+            }
+
+            let mut perturbed = player.clone();
+            perturbed.play_chance = 1.0;
+            // Sorted so the same seed draws the same noise for the same stat
+            let mut names: Vec<String> = perturbed.stats.keys().cloned().collect();
+            names.sort();
+            for name in names {
+                if let Some(Some(value)) = perturbed.stats.get_mut(&name) {
+                    *value *= 1.0 + settings.noise * rng.next_normal();
+                }
+            }
+
+            sampled.push(to_pick_data(&perturbed, reqs));
+            roster_index.push(i);
+        }
+
+        let Some((lineup, _)) = pick_lineup(&sampled, reqs) else {
+            failed_runs += 1;
+            continue;
+        };
+
+        for (data, &i) in sampled.iter().zip(&roster_index) {
+            if lineup.iter().any(|s| s.name == data.name) {
+                starts[i] += 1;
+            }
+        }
+
+        let (offense, defense) = unit_totals(&lineup);
+        totals.push(offense + defense);
+    }
+
+    SimulationResult {
+        starts: players.iter().map(|p| p.name.clone()).zip(starts).collect(),
+        totals,
+        failed_runs,
+        runs: settings.runs,
+    }
+}
+
+// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn print_simulation_report(result: &SimulationResult, baseline_starters: &[String]) {
+    println!("Runs: {}", result.runs);
+    if result.failed_runs > 0 {
+        println!("Runs without enough players: {}", result.failed_runs);
+    }
+
+    if result.totals.is_empty() {
+        return;
+    }
+
+    let mut sorted = result.totals.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let count = sorted.len() as f64;
+    let mean = sorted.iter().sum::<f64>() / count;
+    let sd = (sorted.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / count).sqrt();
+
+    println!("\nTeam total: mean {:.0}, sd {:.1}", mean, sd);
+    println!(
+        "  min {:.0}   5% {:.0}   25% {:.0}   median {:.0}   75% {:.0}   95% {:.0}   max {:.0}",
+        sorted[0],
+        percentile(&sorted, 5.0),
+        percentile(&sorted, 25.0),
+        percentile(&sorted, 50.0),
+        percentile(&sorted, 75.0),
+        percentile(&sorted, 95.0),
+        sorted[sorted.len() - 1],
+    );
+
+    let mut starts: Vec<&(String, usize)> = result.starts.iter().collect();
+    starts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let name_width = starts.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 3;
+
+    println!("\n{:<name_width$}{:>8}", "Player", "Starts");
+    for (name, count) in starts {
+        let marker = if baseline_starters.contains(name) { " *" } else { "" };
+        println!("{:<name_width$}{:>7.1}%{}", name, *count as f64 * 100.0 / result.runs as f64, marker);
+    }
+    println!("\n* In the lineup picked without noise");
+}
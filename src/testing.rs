@@ -1,22 +1,76 @@
 // src/testing.rs
+// Builders shared by the test modules below
+#[cfg(test)]
+pub mod fixtures {
+    use std::fs;
+    use std::io::Result;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::composition::{parse_formations, Formation, PositionRequirements};
+    use crate::evaluator::{evaluate_in, EvalError};
+    use crate::pick::PickTempData;
+    use crate::player::Player;
+
+    // Evaluates a formula without any composition constants.
+    pub fn evaluate(player: &Player, expr: &str) -> std::result::Result<f64, EvalError> {
+        evaluate_in(player, expr, &PositionRequirements::default())
+    }
+
+    // Parses composition text by way of a temporary file, so it goes through
+    // the same path as a real composition.
+    pub fn parse_formations_text(text: &str) -> Result<Vec<Formation>> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "bb_team_picker_{}_{}.txt",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::write(&path, text)?;
+        let formations = parse_formations(&path.to_string_lossy());
+        fs::remove_file(&path)?;
+        formations
+    }
+
+    // One RN and one DL slot, scored by Spd and Str.
+    pub fn one_slot_reqs() -> PositionRequirements {
+        parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Str\n").unwrap().remove(0).1
+    }
+
+    // A player with the given stats, not captain and certain to play.
+    pub fn player(name: &str, stats: &[(&str, f64)]) -> Player {
+        Player {
+            name: name.into(),
+            metadata: String::new(),
+            stats: stats.iter().map(|(stat, value)| (stat.to_string(), Some(*value))).collect(),
+            captain: false,
+            play_chance: 1.0,
+        }
+    }
+
+    // Pick data with the given position scores; its max score is their sum.
+    pub fn pick_data(name: &str, scores: &[(&str, f64)]) -> PickTempData {
+        PickTempData {
+            name: name.into(),
+            position_scores: scores.iter().map(|(pos, score)| (pos.to_string(), *score)).collect(),
+            max_score: scores.iter().map(|(_, score)| score).sum(),
+            captain: false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::evaluator::evaluate;
+    use super::fixtures::{evaluate, player};
     use crate::player::Player;
 
     fn dummy_player() -> Player {
-        let mut stats = std::collections::HashMap::new();
-        stats.insert("Spd".to_string(), 10);
-        stats.insert("Str".to_string(), 20);
-        stats.insert("QB".to_string(), 60);
-        stats.insert("HB".to_string(), 55);
-        stats.insert("BrB".to_string(), 11);
-        stats.insert("Ddg".to_string(), 30);
-        stats.insert("Blk".to_string(), 15);
-        stats.insert("Tck".to_string(), 60);
-        stats.insert("Dur".to_string(), 50);
-        stats.insert("Stm".to_string(), 40);
-        Player { name: "Dummy".into(), stats }
+        let mut p = player("Dummy", &[
+            ("Spd", 10.0), ("Str", 20.0), ("QB", 60.0), ("HB", 55.0), ("BrB", 11.0), ("Ddg", 30.0),
+            ("Blk", 15.0), ("Tck", 60.0), ("Dur", 50.0), ("Stm", 40.0), ("Hnd", 12.5),
+        ]);
+        p.stats.insert("Vis".to_string(), None);
+        p
     }
 
     #[test]
@@ -99,5 +153,1171 @@ mod tests {
         assert!(evaluate(&p, "1 / 0").is_err());
         assert!(evaluate(&p, "unknown_func(5)").is_err());
         assert!(evaluate(&p, "NotAStat").is_err());
+        assert!(evaluate(&p, "Vis + 1").is_err());
+        assert!(evaluate(&p, "default(Spd, Vis)").is_err());
+    }
+
+    #[test]
+    fn test_missing_stats() {
+        let p = dummy_player();
+        assert_eq!(evaluate(&p, "Hnd * 2").unwrap(), 25.0);
+        assert_eq!(evaluate(&p, "ismissing(Vis)").unwrap(), 1.0);
+        assert_eq!(evaluate(&p, "ismissing(Spd)").unwrap(), 0.0);
+        assert_eq!(evaluate(&p, "default(Vis, 30)").unwrap(), 30.0);
+        assert_eq!(evaluate(&p, "default(Spd, 30)").unwrap(), 10.0);
+        assert_eq!(evaluate(&p, "default(Vis * 2, Spd) + 1").unwrap(), 11.0);
+        assert_eq!(evaluate(&p, "default(max(Vis, Str), 0)").unwrap(), 20.0);
+        assert_eq!(evaluate(&p, "if(ismissing(Vis), Str, 0)").unwrap(), 20.0);
+        // IF only evaluates the branch it takes
+        assert_eq!(evaluate(&p, "if(ismissing(Vis), Str, Vis)").unwrap(), 20.0);
+        assert_eq!(evaluate(&p, "if(Spd > 5, Spd, 1 / 0)").unwrap(), 10.0);
+        assert!(evaluate(&p, "if(ismissing(Vis), Vis, Str)").is_err());
+        assert!(evaluate(&p, "if(1, Str, NotAStat)").is_err());
+        assert!(evaluate(&p, "if(1, 2, 3, 4)").is_err());
+    }
+}
+
+#[cfg(test)]
+mod assignment_tests {
+    use crate::assignment::best_assignment;
+    use crate::simulation::Rng;
+
+    // Tries every ordering of the slots.
+    fn brute_force(scores: &[Vec<Option<f64>>], slots: &mut Vec<usize>, index: usize) -> Option<f64> {
+        if index == scores.len() {
+            return Some(0.0);
+        }
+        let mut best: Option<f64> = None;
+        for i in 0..slots.len() {
+            let slot = slots.remove(i);
+            if let Some(score) = scores[index][slot]
+                && let Some(rest) = brute_force(scores, slots, index + 1)
+            {
+                best = Some(best.map_or(score + rest, |b: f64| b.max(score + rest)));
+            }
+            slots.insert(i, slot);
+        }
+        best
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(5);
+        let counts = [3, 2, 1];
+
+        for _ in 0..50 {
+            let scores: Vec<Vec<Option<f64>>> = (0..6)
+                .map(|_| (0..3).map(|_| (rng.next_f64() > 0.2).then(|| (rng.next_f64() * 100.0).round())).collect())
+                .collect();
+            let mut slots = vec![0, 0, 0, 1, 1, 2];
+
+            let expected = brute_force(&scores, &mut slots, 0);
+            let found = best_assignment(&scores, &counts);
+            assert_eq!(found.as_ref().map(|(_, total)| *total), expected);
+
+            // The choices fill every group exactly and add up to the total
+            if let Some((choices, total)) = found {
+                for (group, count) in counts.iter().enumerate() {
+                    assert_eq!(choices.iter().filter(|c| **c == group).count(), *count);
+                }
+                let sum: f64 = choices.iter().enumerate().map(|(i, c)| scores[i][*c].unwrap()).sum();
+                assert_eq!(sum, total);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unplaceable_player() {
+        // Both players can only play the single slot of group 0
+        let scores = vec![vec![Some(10.0), None], vec![Some(20.0), None]];
+        assert!(best_assignment(&scores, &[1, 1]).is_none());
+
+        assert_eq!(best_assignment(&[], &[]), Some((Vec::new(), 0.0)));
+    }
+}
+
+#[cfg(test)]
+mod training_tests {
+    use super::fixtures::{one_slot_reqs, player};
+    use crate::training::recommend_training;
+
+    #[test]
+    fn test_recommend_training() {
+        let reqs = one_slot_reqs();
+        let players = vec![
+            player("Starter", &[("Spd", 60.0), ("Str", 40.0), ("Hnd", 10.0)]),
+            player("Bench", &[("Spd", 50.0), ("Str", 45.0), ("Hnd", 10.0)]),
+        ];
+
+        // Anything the starter trains counts in full; the bench player only
+        // catches up
+        let options = recommend_training(&players, &reqs, 5);
+        assert_eq!(options.len(), 4);
+        assert!(options[..2].iter().all(|o| o.player == "Starter" && o.gain == 5.0));
+        assert!(options[2..].iter().all(|o| o.player == "Bench" && o.gain == 0.0));
+
+        // Enough training lets the bench player take over, gaining the difference
+        let options = recommend_training(&players, &reqs, 10);
+        let bench_spd = options.iter().find(|o| o.player == "Bench" && o.stat == "Spd").unwrap();
+        assert_eq!(bench_spd.gain, 5.0);
+
+        // Stats no formula uses are never suggested
+        assert!(options.iter().all(|o| o.stat != "Hnd"));
+    }
+}
+
+#[cfg(test)]
+mod break_even_tests {
+    use super::fixtures::{one_slot_reqs, parse_formations_text, player};
+    use crate::break_even::{find_break_even, Improvement, Needed};
+
+    #[test]
+    fn test_break_even() {
+        let reqs = one_slot_reqs();
+        let players = vec![
+            player("Starter", &[("Spd", 80.0), ("Str", 40.0)]),
+            player("Bench", &[("Spd", 70.0), ("Str", 45.0)]),
+            player("Hopeless", &[("Spd", 1.0)]),
+        ];
+
+        // Bench is 5 points behind, within the search precision
+        let results = find_break_even(&players, &reqs, &Improvement::PositionScore).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "Bench");
+        let Needed::Points(needed) = results[0].needed else { panic!("Bench should be able to start") };
+        assert!((5.0..=5.5).contains(&needed));
+        assert_eq!(results[1].needed, Needed::OutOfReach);
+
+        // Stat improvements are whole points; a player without the stat can't be
+        // helped by it
+        // Stat names match whatever their case
+        let results = find_break_even(&players, &reqs, &Improvement::Stat("str".to_string())).unwrap();
+        assert!(matches!(results[0].needed, Needed::Points(needed) if needed == 5.0 || needed == 6.0));
+        assert_eq!(results[1].name, "Hopeless");
+        assert_eq!(results[1].needed, Needed::UnknownStat);
+
+        // Everyone starting is different from no lineup at all
+        assert!(find_break_even(&players[..1], &reqs, &Improvement::PositionScore).unwrap().is_empty());
+        let four_slots = parse_formations_text("Offense: RN RN RN RN\nDefense: DL DL DL DL\n").unwrap().remove(0).1;
+        assert!(find_break_even(&players, &four_slots, &Improvement::PositionScore).is_none());
+    }
+}
+
+#[cfg(test)]
+mod transfer_tests {
+    use super::fixtures::{one_slot_reqs, player};
+    use crate::transfer::evaluate_transfers;
+
+    #[test]
+    fn test_transfers() {
+        let reqs = one_slot_reqs();
+        let players = vec![
+            player("Starter", &[("Spd", 80.0), ("Str", 40.0)]),
+            player("Bench", &[("Spd", 70.0), ("Str", 45.0)]),
+        ];
+        let candidates = vec![
+            player("Pricey", &[("Spd", 90.0), ("Str", 60.0), ("Price", 25.0)]),
+            // Shares a name with the starter, but is a different, better player
+            player("Starter", &[("Spd", 85.0), ("Str", 45.0)]),
+            player("Weak", &[("Spd", 10.0), ("Str", 10.0), ("Price", 0.0)]),
+        ];
+
+        let options = evaluate_transfers(&players, &candidates, &reqs, Some("price"));
+        let option = |name: &str| options.iter().find(|o| o.name == name).unwrap();
+
+        assert_eq!(option("Starter").gain, 10.0);
+        assert_eq!(option("Starter").displaced, vec!["Starter"]);
+        assert_eq!(option("Weak").gain, 0.0);
+        assert!(option("Weak").displaced.is_empty());
+
+        // Priced candidates rank by net value; the unpriced namesake can't be
+        // netted, so it comes last despite its gain
+        assert_eq!(option("Pricey").gain, 30.0);
+        assert_eq!(option("Pricey").net(), Some(5.0));
+        assert_eq!(option("Starter").net(), None);
+        let names: Vec<&str> = options.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["Pricey", "Weak", "Starter"]);
+
+        // Without a price column it's gain alone
+        let options = evaluate_transfers(&players, &candidates, &reqs, None);
+        assert_eq!(options[0].name, "Pricey");
+        assert_eq!(options[1].name, "Starter");
+    }
+}
+
+#[cfg(test)]
+mod release_tests {
+    use super::fixtures::{one_slot_reqs, player};
+    use crate::release::marginal_values;
+
+    #[test]
+    fn test_marginal_values() {
+        let reqs = one_slot_reqs();
+        let players = vec![
+            player("Starter", &[("Spd", 80.0), ("Str", 40.0)]),
+            player("Bench", &[("Spd", 70.0), ("Str", 45.0)]),
+            player("Spare", &[("Spd", 10.0), ("Str", 10.0)]),
+        ];
+
+        let values = marginal_values(&players, &reqs);
+        let names: Vec<&str> = values.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["Spare", "Bench", "Starter"]);
+
+        // The spare covers nothing the bench doesn't
+        assert_eq!((values[0].team_drop, values[0].bench_drop), (Some(0.0), 0.0));
+        // Losing the bench player leaves only the spare to cover RN and DL
+        assert_eq!((values[1].team_drop, values[1].bench_drop), (Some(0.0), 95.0));
+        // Losing the starter costs the gap to their replacement
+        assert!(values[2].starter);
+        assert_eq!(values[2].team_drop, Some(5.0));
+
+        // Without anyone to step in there's no replacement at all
+        let values = marginal_values(&players[..1], &reqs);
+        assert_eq!(values[0].team_drop, None);
+    }
+}
+
+#[cfg(test)]
+mod compare_tests {
+    use super::fixtures::{parse_formations_text, pick_data};
+    use crate::compare::{compare_lineups, comparison_to_json};
+    use crate::json::JsonValue;
+    use crate::lineup::pick_lineup;
+
+    #[test]
+    fn test_compare_lineups() {
+        let reqs = parse_formations_text("Offense: RN RN\nDefense: DL DL\n").unwrap().remove(0).1;
+        let ours = vec![pick_data("A", &[("RN", 50.0), ("DL", 30.0)]), pick_data("B", &[("RN", 40.0), ("DL", 20.0)])];
+        let theirs = vec![pick_data("C", &[("RN", 45.0), ("DL", 35.0)]), pick_data("D", &[("RN", 30.0), ("DL", 10.0)])];
+        let (ours, _) = pick_lineup(&ours, &reqs).unwrap();
+        let (theirs, _) = pick_lineup(&theirs, &reqs).unwrap();
+
+        // Strongest against strongest within each position
+        let comparison = compare_lineups("Us", &ours, "Them", &theirs, &reqs);
+        let advantages: Vec<f64> = comparison.slots.iter().map(|s| s.advantage()).collect();
+        assert_eq!(advantages, vec![5.0, 10.0, -5.0, 10.0]);
+        assert_eq!(comparison.slots[0].ours, Some(("A".to_string(), 50.0)));
+        assert_eq!(comparison.position_advantages(), vec![("RN".to_string(), 15.0), ("DL".to_string(), 5.0)]);
+
+        let json = JsonValue::parse(&comparison_to_json(&comparison).to_pretty_string()).unwrap();
+        assert_eq!(json.get("ours").and_then(JsonValue::as_str), Some("Us"));
+        assert_eq!(json.get("our_totals").and_then(|t| t.get("total")), Some(&JsonValue::Number(140.0)));
+        assert_eq!(json.get("their_totals").and_then(|t| t.get("defense")), Some(&JsonValue::Number(45.0)));
+        assert_eq!(json.get("position_advantages").and_then(|a| a.get("DL")), Some(&JsonValue::Number(5.0)));
+        let Some(JsonValue::Array(slots)) = json.get("slots") else { panic!("slots should be an array") };
+        assert_eq!(slots.len(), 4);
+    }
+}
+
+#[cfg(test)]
+mod league_tests {
+    use super::fixtures::one_slot_reqs;
+    use crate::league::{format_league_csv, league_table};
+
+    #[test]
+    fn test_league_table() {
+        let reqs = one_slot_reqs();
+
+        // broken.json can't be read and is skipped
+        let table = league_table("testing/league", &reqs).unwrap();
+        let teams: Vec<&str> = table.iter().map(|e| e.team.as_str()).collect();
+        assert_eq!(teams, vec!["beta", "alpha"]);
+        assert_eq!(table[0].total(), 120.0);
+        assert_eq!(table[1].positions, vec![("RN".to_string(), 60.0), ("DL".to_string(), 40.0)]);
+
+        assert_eq!(
+            format_league_csv(&table),
+            "Rank,Team,Offense,Defense,Total,RN,DL\n\
+            1,beta,70.0,50.0,120.0,70.0,50.0\n\
+            2,alpha,60.0,40.0,100.0,60.0,40.0\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod roster_tests {
+    use crate::roster::{
+        parse_delimited, parse_json_roster, parse_paste, parse_paste_with_notes, parse_stat_value,
+        roster_to_json,
+    };
+
+    #[test]
+    fn test_paste_format() {
+        let text = "Name XP Spd Str\n\
+            Steve Morrey\n\
+            #7 Common Troll\t12\t73\t59\n\
+            [CAPTAIN] Daxxter Hijack\n\
+            #1 Common Human\t8\t60\t45\n";
+        let players = parse_paste(text).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].name, "Steve Morrey");
+        assert_eq!(players[0].metadata, "#7 Common Troll");
+        assert_eq!(players[0].stats["Spd"], Some(73.0));
+        assert_eq!(players[1].name, "Daxxter Hijack");
+        assert_eq!(players[1].stats["Str"], Some(45.0));
+        assert!(players[1].captain);
+        assert!(!players[0].captain);
+    }
+
+    #[test]
+    fn test_mangled_paste() {
+        let text = "Name XP Spd Str\r\n\
+            Steve Morrey\r\n\
+            \r\n\
+            #7 Common Troll   12   73   59\r\n\
+            \r\n\
+            Ed Big-Mother\r\n\
+            #22 Subterranean Minotaur\t9 57  85\r\n\
+            Julio Lobster\r\n\
+            #93 Common Drakon\t9\t70\t81\r\n";
+        let (players, notes) = parse_paste_with_notes(text).unwrap();
+
+        assert_eq!(players.len(), 3);
+        assert_eq!(players[0].name, "Steve Morrey");
+        assert_eq!(players[0].metadata, "#7 Common Troll");
+        assert_eq!(players[0].stats["Str"], Some(59.0));
+        assert_eq!(players[1].metadata, "#22 Subterranean Minotaur");
+        assert_eq!(players[1].stats["Spd"], Some(57.0));
+        assert_eq!(players[2].stats["Spd"], Some(70.0));
+        assert_eq!(notes.len(), 3);
+    }
+
+    #[test]
+    fn test_paste_skips_players_without_stats() {
+        let text = "Name Spd Str\n\
+            No Stats Here\n\
+            Steve Morrey\n\
+            #7 Common Troll\t73\t59\n\
+            Too Few\n\
+            #1 Common Human\t73\n";
+        let (players, notes) = parse_paste_with_notes(text).unwrap();
+
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].name, "Steve Morrey");
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_decimal_and_missing_values() {
+        let text = "Name Spd Str Vis\n\
+            Steve Morrey\n\
+            #7 Common Troll\t73.5\t-\t12,5\n\
+            Ed Big-Mother\n\
+            #22 Subterranean Minotaur 57 - ?\n";
+        let (players, _) = parse_paste_with_notes(text).unwrap();
+
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].stats["Spd"], Some(73.5));
+        assert_eq!(players[0].stats["Str"], None);
+        assert_eq!(players[0].stats["Vis"], Some(12.5));
+        assert_eq!(players[1].stats["Spd"], Some(57.0));
+        assert_eq!(players[1].stats["Vis"], None);
+
+        let json = roster_to_json(&players).to_pretty_string();
+        assert_eq!(parse_json_roster(&json).unwrap()[0].stats, players[0].stats);
+    }
+
+    #[test]
+    fn test_decimal_commas() {
+        assert_eq!(parse_stat_value("12,5"), Some(Some(12.5)));
+        assert_eq!(parse_stat_value("12,25"), Some(Some(12.25)));
+        // A thousands separator, not a decimal
+        assert_eq!(parse_stat_value("1,234"), None);
+        assert_eq!(parse_stat_value("1,2,3"), None);
+        assert_eq!(parse_stat_value("1.5,2"), None);
+        assert_eq!(parse_stat_value("12,"), None);
+    }
+
+    #[test]
+    fn test_delimited_formats() {
+        let csv = "Name,Spd,Str\nSteve Morrey,73,59\n\"Lobster, Julio\",66,70\n";
+        let players = parse_delimited(csv).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[1].name, "Lobster, Julio");
+        assert_eq!(players[1].stats["Str"], Some(70.0));
+
+        let tsv = "Spd\tName\tStr\r\n73\tSteve Morrey\t59\r\n";
+        let players = parse_delimited(tsv).unwrap();
+        assert_eq!(players[0].name, "Steve Morrey");
+        assert_eq!(players[0].stats["Spd"], Some(73.0));
+        assert_eq!(players[0].stats["Str"], Some(59.0));
+
+        let semicolons = "Name;Spd\nSteve Morrey;73\n";
+        assert_eq!(parse_delimited(semicolons).unwrap()[0].stats["Spd"], Some(73.0));
+    }
+
+    #[test]
+    fn test_missing_name_column() {
+        assert!(parse_delimited("Spd,Str\n1,2\n").is_err());
+        assert!(parse_paste("Spd Str\nA\nx\t1\t2\n").is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let text = "Name XP Spd\nSteve Morrey\n#7 Common Troll\t12\t73\n[CAPTAIN] Julio Lobster\n#93 Common Drakon\t9\t70\n";
+        let players = parse_paste(text).unwrap();
+
+        let json = roster_to_json(&players).to_pretty_string();
+        let parsed = parse_json_roster(&json).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "Steve Morrey");
+        assert_eq!(parsed[0].metadata, "#7 Common Troll");
+        assert_eq!(parsed[0].stats, players[0].stats);
+        assert!(!parsed[0].captain);
+        assert_eq!(parsed[1].name, "Julio Lobster");
+        assert!(parsed[1].captain);
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert!(parse_json_roster("{\"name\": \"x\"}").is_err());
+        assert!(parse_json_roster("[{\"name\": \"x\"").is_err());
+        assert_eq!(parse_json_roster("[{\"stats\": {}}]").unwrap().len(), 0);
+    }
+}
+
+
+#[cfg(test)]
+mod html_roster_tests {
+    use crate::roster_html::parse_html_roster;
+
+    #[test]
+    fn test_saved_team_page() {
+        let players = parse_html_roster(include_str!("../testing/team_page.html")).unwrap();
+        assert_eq!(players.len(), 3);
+
+        assert_eq!(players[0].name, "Steve Morrey");
+        assert_eq!(players[0].metadata, "#7 Common Troll");
+        assert_eq!(players[0].stats["Spd"], Some(73.0));
+        assert_eq!(players[0].stats["Dur"], Some(84.0));
+        assert!(!players[0].captain);
+
+        // The captain badge element flags the player and isn't part of the name
+        assert_eq!(players[1].name, "Daxxter Hijack");
+        assert!(players[1].captain);
+        assert_eq!(players[1].metadata, "#13 Common Human");
+
+        assert_eq!(players[2].name, "Ed Big-Mother & Co");
+        assert_eq!(players[2].metadata, "#22 Subterranean Minotaur");
+        assert_eq!(players[2].stats["Str"], Some(85.0));
+        assert!(!players[2].captain);
+    }
+
+    #[test]
+    fn test_flat_team_page() {
+        let players = parse_html_roster(include_str!("../testing/team_page_flat.html")).unwrap();
+        assert_eq!(players.len(), 2);
+
+        // A [CAPTAIN] prefix flags the player too
+        assert_eq!(players[0].name, "Ross Puke");
+        assert!(players[0].captain);
+        assert!(!players[1].captain);
+        assert_eq!(players[0].metadata, "#11 Common Celestial");
+        assert_eq!(players[0].stats["Spd"], Some(64.0));
+        assert_eq!(players[1].name, "Julio Lobster");
+        assert_eq!(players[1].stats["Str"], Some(81.0));
+    }
+
+    #[test]
+    fn test_captain_badge_classes() {
+        let html = "<table><tr><th>Name</th><th>Spd</th></tr>\
+            <tr><td>Anna <span class='badge captain'>C</span></td><td>50</td></tr>\
+            <tr><td>Bert <span class=\"vice-captain\">VC</span></td><td>40</td></tr>\
+            <tr><td>Cara <a title=\"Captain's notes\" data-role=\"captain\">Cara</a></td><td>30</td></tr>\
+            </table>";
+        let players = parse_html_roster(html).unwrap();
+
+        assert_eq!(players[0].name, "Anna");
+        assert!(players[0].captain);
+        // Only a captain class counts, not other mentions of the word
+        assert!(!players[1].captain);
+        assert!(!players[2].captain);
+    }
+
+    #[test]
+    fn test_page_without_roster() {
+        assert!(parse_html_roster("<html><table><tr><td>Home</td></tr></table></html>").is_err());
+    }
+}
+
+
+#[cfg(test)]
+mod composition_tests {
+    use crate::composition::{
+        formations_to_json, parse_formations, parse_formations_json, shadowed_stats, PositionRequirements,
+    };
+    use crate::lineup::{explain_infeasible, pick_lineup};
+    use crate::objective::Objective;
+    use crate::pick::{to_pick_data, PickTempData};
+    use crate::captain::rank_captains;
+    use crate::formations::compare_formations;
+    use super::fixtures::{parse_formations_text, pick_data, player};
+
+    fn parse_composition_text(text: &str) -> PositionRequirements {
+        parse_formations_text(text).unwrap().remove(0).1
+    }
+
+    fn parse_composition_json(text: &str) -> std::io::Result<PositionRequirements> {
+        Ok(parse_formations_json(text)?.remove(0).1)
+    }
+
+    #[test]
+    fn test_text_composition() {
+        let reqs = parse_composition_text(include_str!("../testing/composition_comments.txt"));
+        assert_eq!(reqs.attacking.len(), 8);
+        assert_eq!(reqs.defensive.len(), 8);
+        assert_eq!(reqs.position_to_calculation["RN"], "max(HB,QB)");
+        assert_eq!(reqs.position_to_calculation["BK"], "BK * 70 / min(Dur, 70)");
+    }
+
+    #[test]
+    fn test_json_composition() {
+        let json = r#"{
+            "metadata": { "name": "Heavy", "author": "me" },
+            "offense": ["RN", "GN"],
+            "defense": ["DL", "LB"],
+            "formulas": { "RN": "max(HB, QB) // not a comment" },
+            "weights": { "DL": 1.5 },
+            "constraints": { "DL": "Dur >= 50" }
+        }"#;
+        let reqs = parse_composition_json(json).unwrap();
+
+        assert_eq!(reqs.name(), Some("Heavy"));
+        assert_eq!(reqs.attacking, vec!["RN", "GN"]);
+        assert_eq!(reqs.position_to_calculation["RN"], "max(HB, QB) // not a comment");
+        assert_eq!(reqs.weight("DL"), 1.5);
+        assert_eq!(reqs.weight("LB"), 1.0);
+        assert_eq!(reqs.eligibility["DL"], "Dur >= 50");
+
+        assert!(parse_composition_json(r#"{ "offense": "RN" }"#).is_err());
+        assert!(parse_composition_json(r#"{ "weights": { "DL": "heavy" } }"#).is_err());
+    }
+
+    #[test]
+    fn test_composition_conversion() {
+        let formations = parse_formations_text(include_str!("../testing/composition_formations.txt")).unwrap();
+        let converted = parse_formations_json(&formations_to_json(&formations).to_pretty_string()).unwrap();
+
+        assert_eq!(converted.len(), formations.len());
+        for ((name, reqs), (converted_name, converted)) in formations.iter().zip(&converted) {
+            assert_eq!(converted_name, name);
+            assert_eq!(converted.attacking, reqs.attacking);
+            assert_eq!(converted.defensive, reqs.defensive);
+            assert_eq!(converted.position_to_calculation, reqs.position_to_calculation);
+        }
+    }
+
+    #[test]
+    fn test_named_formations() {
+        let formations = parse_formations_text(include_str!("../testing/composition_formations.txt")).unwrap();
+        let names: Vec<&str> = formations.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Standard", "Heavy", "Spread"]);
+
+        let (_, standard) = &formations[0];
+        assert_eq!(standard.attacking.len(), 8);
+        assert_eq!(standard.position_to_calculation["BK"], "BK");
+
+        // Own defense and formula override, shared offense
+        let (_, heavy) = &formations[1];
+        assert_eq!(heavy.attacking, standard.attacking);
+        assert_eq!(heavy.defensive, vec!["DL", "DL", "DL", "DL", "CV", "LB", "LB", "LB"]);
+        assert_eq!(heavy.position_to_calculation["DL"], "DL * 1.1");
+        assert_eq!(heavy.position_to_calculation["RN"], "max(HB,QB)");
+
+        let (_, spread) = &formations[2];
+        assert_eq!(spread.attacking, vec!["RN", "RN", "RN", "RN", "GN", "GN", "BK", "BK"]);
+        assert_eq!(spread.position_to_calculation["DL"], "DL");
+
+        // No sections means one default formation
+        let single = parse_formations_text("Offense: RN\nDefense: DL\n").unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].0, "Default");
+    }
+
+    #[test]
+    fn test_unbalanced_formation() {
+        // The override only changes the offense, leaving nine against eight
+        let text = "Offense: RN RN RN RN GN GN BK BK\n\
+            Defense: DL DL DL CV CV LB LB LB\n\
+            Formation: Heavy\n\
+            Offense: RN RN RN RN GN GN BK BK BK\n";
+        let err = parse_formations_text(text).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Formation 'Heavy' has 9 offense slots but 8 defense slots");
+
+        let json = r#"{"offense": ["RN"], "defense": ["DL"], "formations": {"Wide": {"defense": ["DL", "CV"]}}}"#;
+        let err = parse_formations_json(json).unwrap_err();
+        assert!(err.to_string().contains("Formation 'Wide' has 1 offense slots but 2 defense slots"));
+    }
+
+    #[test]
+    fn test_composition_include() {
+        let formations = parse_formations("testing/includes/main.txt").unwrap();
+        let (_, reqs) = &formations[0];
+
+        // Included slots and formulas, overridden by the including file
+        assert_eq!(reqs.attacking, vec!["RN", "RN", "RN", "RN", "GN", "GN", "BK", "BK"]);
+        assert_eq!(reqs.defensive, vec!["DL", "DL", "DL", "CV", "CV", "LB", "LB", "LB"]);
+        assert_eq!(reqs.position_to_calculation["RN"], "max(HB,QB)");
+        assert_eq!(reqs.position_to_calculation["BK"], "BK * 70 / min(Dur, 70)");
+
+        let cycle = parse_formations("testing/includes/cycle_a.txt").unwrap_err();
+        assert!(cycle.to_string().contains("Include cycle"));
+
+        let missing = parse_formations("testing/includes/missing_include.txt").unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_slot_weights() {
+        let reqs = parse_composition_text("Offense: RN*1.5 RN\nDefense: DL DL\nWeights: DL*2\n");
+        assert_eq!(reqs.attacking, vec!["RN", "RN"]);
+        assert_eq!(reqs.attacking_weights, vec![1.5, 1.0]);
+        assert_eq!(reqs.weight("DL"), 2.0);
+        assert!(parse_formations_text("Offense: RN*lots\n").is_err());
+
+        // A negative weight would make the optimizer avoid the position
+        assert!(parse_formations_text("Offense: RN*-2\n").is_err());
+        assert!(parse_formations_text("Weights: DL*-1\n").is_err());
+        assert!(parse_composition_json(r#"{ "offense": ["RN*-2"] }"#).is_err());
+        assert!(parse_composition_json(r#"{ "weights": { "DL": -1 } }"#).is_err());
+
+        // The stronger runner takes the weighted slot
+        let players = vec![pick_data("Weak", &[("RN", 40.0), ("DL", 10.0)]), pick_data("Strong", &[("RN", 80.0), ("DL", 10.0)])];
+        let (lineup, total) = pick_lineup(&players, &reqs).unwrap();
+
+        let strong = lineup.iter().find(|s| s.name == "Strong").unwrap();
+        assert_eq!(strong.offense.weight, 1.5);
+        assert_eq!(strong.offense.weighted_score(), 120.0);
+        assert_eq!(total, 80.0 * 1.5 + 40.0 + 2.0 * 10.0 * 2.0);
+
+        let json = formations_to_json(&[("Default".to_string(), reqs)]).to_pretty_string();
+        assert_eq!(parse_composition_json(&json).unwrap().attacking_weights, vec![1.5, 1.0]);
+    }
+
+    #[test]
+    fn test_objectives() {
+        assert_eq!(Objective::parse("maximin"), Ok(Objective::Maximin));
+        assert_eq!(Objective::parse("Variance 0.5"), Ok(Objective::Variance(0.5)));
+        assert_eq!(Objective::parse("mix 0.6"), Ok(Objective::Mix(0.6)));
+        assert!(Objective::parse("mix 1.5").is_err());
+        assert!(Objective::parse("variance").is_err());
+        assert!(Objective::parse("maximin 2").is_err());
+        assert!(Objective::parse("median").is_err());
+
+        let slots = [10.0, 30.0, 20.0, 40.0];
+        assert_eq!(Objective::Sum.score(40.0, 60.0, &slots), 100.0);
+        assert!((Objective::Maximin.score(40.0, 60.0, &slots) - 10.0).abs() < 0.01);
+        assert_eq!(Objective::Variance(0.1).score(40.0, 60.0, &slots), 100.0 - 0.1 * 125.0);
+        assert_eq!(Objective::Mix(0.25).score(40.0, 60.0, &slots), 10.0 + 45.0);
+
+        let reqs = parse_composition_text("Offense: RN\nDefense: DL\nObjective: variance 2\n");
+        assert_eq!(reqs.objective(), Objective::Variance(2.0));
+        assert_eq!(parse_composition_text("Offense: RN\nDefense: DL\n").objective(), Objective::Sum);
+        assert!(parse_formations_text("Objective: best\n").is_err());
+
+        let json = formations_to_json(&[("Default".to_string(), reqs)]).to_pretty_string();
+        assert_eq!(parse_composition_json(&json).unwrap().objective(), Objective::Variance(2.0));
+    }
+
+    #[test]
+    fn test_unit_settings() {
+        let reqs = parse_composition_text("Offense: RN\nDefense: DL\nDefense weight: 2\nOffense minimum: 30\n");
+        assert_eq!(reqs.defense_unit.weight(), 2.0);
+        assert_eq!(reqs.offense_unit.weight(), 1.0);
+        assert_eq!(reqs.offense_unit.minimum, Some(30.0));
+        assert!(parse_formations_text("Defense weight: heavy\n").is_err());
+        assert!(parse_formations_text("Defense weight: -2\n").is_err());
+        assert!(parse_composition_json(r#"{ "units": { "defense": { "weight": -2 } } }"#).is_err());
+        assert!(parse_composition_json(r#"{ "units": { "offense": { "minimum": -30 } } }"#).is_err());
+
+        let json = formations_to_json(&[("Default".to_string(), reqs.clone())]).to_pretty_string();
+        let converted = parse_composition_json(&json).unwrap();
+        assert_eq!(converted.defense_unit, reqs.defense_unit);
+        assert_eq!(converted.offense_unit, reqs.offense_unit);
+
+        let players = vec![pick_data("Runner", &[("RN", 100.0), ("DL", 10.0)]), pick_data("Allrounder", &[("RN", 50.0), ("DL", 50.0)])];
+        let starter = |reqs: &PositionRequirements| pick_lineup(&players, reqs).unwrap().0[0].name.clone();
+
+        let plain = parse_composition_text("Offense: RN\nDefense: DL\n");
+        assert_eq!(starter(&plain), "Runner");
+        assert_eq!(starter(&parse_composition_text("Offense: RN\nDefense: DL\nDefense weight: 2\n")), "Allrounder");
+        assert_eq!(starter(&parse_composition_text("Offense: RN\nDefense: DL\nDefense minimum: 40\n")), "Allrounder");
+
+        // Nobody meets the minimum: the smallest shortfall starts, and the
+        // score is the lineup's own, not the optimizer's penalty
+        let unreachable = parse_composition_text("Offense: RN\nDefense: DL\nDefense minimum: 70\n");
+        let (lineup, score) = pick_lineup(&players, &unreachable).unwrap();
+        assert_eq!(lineup[0].name, "Allrounder");
+        assert_eq!(score, 100.0);
+    }
+
+    #[test]
+    fn test_formation_ranking() {
+        let text = "Offense: RN\nDefense: DL\nRN=Spd\nDL=Str\n\
+            Formation: Plain\n\
+            Formation: Heavy\n\
+            Defense weight: 3\n";
+        let formations = parse_formations_text(text).unwrap();
+        let players = vec![
+            player("Runner", &[("Spd", 100.0), ("Str", 10.0)]),
+            player("Allrounder", &[("Spd", 50.0), ("Str", 50.0)]),
+        ];
+
+        // Heavy's weighted score (200) is higher, but Plain fields the
+        // stronger raw lineup (110 against 100)
+        let results = compare_formations(&players, &formations);
+        assert_eq!(results[0].name, "Plain");
+        assert_eq!(results[0].lineup.as_ref().unwrap().1, 110.0);
+        assert_eq!(results[1].name, "Heavy");
+        assert_eq!(results[1].lineup.as_ref().unwrap().1, 200.0);
+
+        // A full roster that breaks the rules isn't short of players
+        let strict = parse_formations_text("Offense: RN\nDefense: DL\nDL requires Str > 90\n\
+            Formation: Strict\nFormation: Large\nOffense: RN RN RN\nDefense: DL DL DL\n").unwrap();
+        let results = compare_formations(&players, &strict);
+        let reason = |name: &str| results.iter().find(|r| r.name == name).unwrap().lineup.as_ref().err().copied();
+        assert_eq!(reason("Strict"), Some("no lineup fits the rules"));
+        assert_eq!(reason("Large"), Some("not enough players"));
+    }
+
+    #[test]
+    fn test_shadowed_stats() {
+        let formations = parse_formations_text("Offense: RN\nDefense: DL\nconst SPD = 50\nconst Cut = 10\n").unwrap();
+        let players = vec![player("Runner", &[("Spd", 80.0), ("Str", 40.0)])];
+        assert_eq!(shadowed_stats(&formations, &players), vec!["SPD"]);
+    }
+
+    #[test]
+    fn test_rank_captains() {
+        let reqs = parse_composition_text(
+            "Offense: RN RN\nDefense: DL DL\nRN=Spd\nDL=Spd\nCaptain formula: pow(Spd - 50, 0.5)\n"
+        );
+        let players = vec![player("Slow", &[("Spd", 40.0)]), player("Fast", &[("Spd", 66.0)])];
+        let pick: Vec<_> = players.iter().map(|p| to_pick_data(p, &reqs)).collect();
+        let (lineup, _) = pick_lineup(&pick, &reqs).unwrap();
+
+        // Slow's rating isn't a number, so only Fast is ranked
+        assert_eq!(rank_captains(&players, &lineup, &reqs), vec![("Fast".to_string(), 4.0)]);
+    }
+
+    #[test]
+    fn test_eligibility_rules() {
+        let reqs = parse_composition_text(
+            "Offense: RN RN\nDefense: DL DL\nDL requires Dur >= 50\nDL REQUIRES Str > 10\nRN=max(HB,QB)\n"
+        );
+        assert_eq!(reqs.eligibility["DL"], "(Dur >= 50) && (Str > 10)");
+        assert_eq!(reqs.position_to_calculation["RN"], "max(HB,QB)");
+
+        // Only "Sturdy" may play DL, and two DL slots need two eligible players
+        // Everyone looks equally good up front
+        let player = |name: &str, scores: &[(&str, f64)]| PickTempData { max_score: 100.0, ..pick_data(name, scores) };
+        let players = vec![
+            player("Star", &[("RN", 50.0)]), player("Fragile", &[("RN", 50.0)]), player("Sturdy", &[("RN", 50.0), ("DL", 30.0)]),
+        ];
+        assert!(pick_lineup(&players, &reqs).is_none());
+
+        let reasons = explain_infeasible(&players, &reqs);
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].starts_with("DL: 2 defense slot(s) but only 1 eligible player(s)"));
+
+        // Too few players is a different problem from the rules
+        assert_eq!(explain_infeasible(&players[..1], &reqs), vec!["Only 1 player(s) for 2 lineup places"]);
+
+        // One DL slot: the eligible player starts even though the others are listed first
+        let single = parse_composition_text("Offense: RN RN\nDefense: DL RN\n");
+        let (lineup, _) = pick_lineup(&players, &single).unwrap();
+        assert!(lineup.iter().any(|s| s.name == "Sturdy" && s.defense.position == "DL"));
+    }
+
+    #[test]
+    fn test_captain_settings() {
+        let reqs = parse_composition_text("Offense: RN\nDefense: DL\nCaptain: required\nCaptain formula: Vis + Bru\n");
+        assert!(reqs.captain.required());
+        assert_eq!(reqs.captain.formula.as_deref(), Some("Vis + Bru"));
+        assert!(parse_formations_text("Captain: sometimes\n").is_err());
+
+        let json = formations_to_json(&[("Default".to_string(), reqs.clone())]).to_pretty_string();
+        assert_eq!(parse_composition_json(&json).unwrap().captain, reqs.captain);
+
+        // The captain starts even though the other two score higher
+        let player = |name: &str, score: f64, captain: bool| PickTempData {
+            captain,
+            ..pick_data(name, &[("RN", score), ("DL", score)])
+        };
+        let players = vec![player("Star", 90.0, false), player("Backup", 80.0, false), player("Skipper", 40.0, true)];
+        let (lineup, _) = pick_lineup(&players, &reqs).unwrap();
+        assert_eq!(lineup[0].name, "Skipper");
+        assert!(lineup[0].captain);
+
+        // Without a captain on the roster the requirement is ignored
+        let (lineup, total) = pick_lineup(&players[..2], &reqs).unwrap();
+        assert_eq!(lineup[0].name, "Star");
+        assert_eq!(total, 180.0);
+
+        // A captain who can't play defense can't start, so nothing satisfies it
+        let benched = vec![
+            player("Star", 90.0, false),
+            PickTempData { captain: true, ..pick_data("Skipper", &[("RN", 40.0)]) },
+        ];
+        assert!(pick_lineup(&benched, &reqs).is_none());
+        let reasons = explain_infeasible(&benched, &reqs);
+        assert_eq!(reasons, vec!["The captain, Skipper, can't fill any eligible defense slot"]);
+
+        // No slots at all
+        let empty = parse_composition_text("Captain: required\n");
+        assert!(pick_lineup(&players, &empty).is_none());
+        assert_eq!(explain_infeasible(&players, &empty), vec!["The composition has no slots to fill"]);
+    }
+}
+
+#[cfg(test)]
+mod rotation_tests {
+    use crate::availability::Availability;
+    use super::fixtures::{parse_formations_text, player};
+    use crate::rotation::{max_consecutive_starts, plan_rotation};
+
+    #[test]
+    fn test_rotation_rests_tired_players() {
+        let reqs = parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Str\nStamina formula: Stm / 10\n")
+            .unwrap().remove(0).1;
+        let players = vec![player("Star", &[("Spd", 90.0), ("Str", 90.0), ("Stm", 15.0)]), player("Backup", &[("Spd", 50.0), ("Str", 50.0), ("Stm", 40.0)]), player("Reserve", &[("Spd", 40.0), ("Str", 40.0), ("Stm", 40.0)])];
+
+        assert_eq!(max_consecutive_starts(&players[0], &reqs), 1);
+        assert_eq!(max_consecutive_starts(&player("Spent", &[("Spd", 50.0), ("Str", 50.0), ("Stm", 0.0)]), &reqs), 1);
+
+        let plan = plan_rotation(&players, &reqs, 3, &Availability::default());
+        let starters: Vec<&str> = plan.matches.iter().map(|m| m.lineup[0].name.as_str()).collect();
+
+        // The star can't start twice in a row, so they play matches 1 and 3
+        assert_eq!(starters, vec!["Star", "Backup", "Star"]);
+        assert!(plan.matches.iter().all(|m| m.overworked.is_empty()));
+    }
+
+    #[test]
+    fn test_rotation_without_enough_rested_players() {
+        let reqs = parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Str\nStamina formula: 1\n")
+            .unwrap().remove(0).1;
+        let players = vec![player("Only", &[("Spd", 90.0), ("Str", 90.0), ("Stm", 10.0)])];
+
+        let plan = plan_rotation(&players, &reqs, 2, &Availability::default());
+        assert_eq!(plan.matches.len(), 2);
+        assert_eq!(plan.matches[1].overworked, vec!["Only"]);
+        assert!(plan.unfilled.is_empty());
+    }
+
+    #[test]
+    fn test_rotation_that_breaks_the_rules() {
+        let reqs = parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Str\nDL requires Str > 95\n")
+            .unwrap().remove(0).1;
+        let players = vec![player("Only", &[("Spd", 90.0), ("Str", 90.0)])];
+
+        // A full roster, but nobody may play DL
+        let plan = plan_rotation(&players, &reqs, 2, &Availability::default());
+        assert!(plan.matches.is_empty());
+        assert_eq!(plan.unfilled.len(), 1);
+        assert!(plan.unfilled[0].starts_with("DL: 1 defense slot(s) but only 0 eligible player(s)"));
+    }
+}
+
+#[cfg(test)]
+mod availability_tests {
+    use crate::availability::{parse_availability, DoubtfulHandling, Status};
+    use super::fixtures::{parse_formations_text, player};
+    use crate::rotation::plan_rotation;
+
+    #[test]
+    fn test_availability_file() {
+        let availability = parse_availability(
+            "# Before the cup games\n\
+            Steve Morrey: injured 2\n\
+            Ross Puke: Suspended\n\
+            Julio Lobster: doubtful 0.25\n\
+            Diego ASMR: doubtful 60%\n\
+            Hank Donkk: available\n"
+        ).unwrap();
+
+        assert_eq!(availability.status("steve morrey"), Status::Injured(2));
+        assert_eq!(availability.status("Ross Puke"), Status::Suspended(1));
+        assert_eq!(availability.status("Diego ASMR"), Status::Doubtful(0.6));
+        assert_eq!(availability.status("Not Listed"), Status::Available);
+        assert_eq!(availability.matches_missed("Steve Morrey"), 2);
+
+        assert!(parse_availability("Steve Morrey: injured\n").is_err());
+        assert!(parse_availability("Steve Morrey: doubtful 1.5\n").is_err());
+        assert!(parse_availability("Steve Morrey: tired\n").is_err());
+        assert!(parse_availability("Steve Morrey\n").is_err());
+
+        let players = vec![player("Steve Morrey", &[("Spd", 1.0)]), player("Julio Lobster", &[("Spd", 1.0)]), player("Hank Donkk", &[("Spd", 1.0)])];
+
+        let expected = availability.remove_unavailable(availability.apply_doubtful(players.clone(), DoubtfulHandling::Expected));
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected[0].name, "Julio Lobster");
+        assert_eq!(expected[0].play_chance, 0.25);
+
+        let excluded = availability.apply_doubtful(players, DoubtfulHandling::Exclude);
+        assert!(excluded.iter().all(|p| p.name != "Julio Lobster"));
+    }
+
+    #[test]
+    fn test_rotation_waits_for_injured_players() {
+        let reqs = parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Spd\nStamina formula: 5\n")
+            .unwrap().remove(0).1;
+        let players = vec![player("Star", &[("Spd", 90.0)]), player("Backup", &[("Spd", 50.0)])];
+        let availability = parse_availability("Star: injured 2\n").unwrap();
+
+        let plan = plan_rotation(&players, &reqs, 3, &availability);
+        let starters: Vec<&str> = plan.matches.iter().map(|m| m.lineup[0].name.as_str()).collect();
+        assert_eq!(starters, vec!["Backup", "Backup", "Star"]);
+    }
+}
+
+#[cfg(test)]
+mod simulation_tests {
+    use super::fixtures::{parse_formations_text, player};
+    use crate::player::Player;
+    use crate::simulation::{simulate, Rng, SimulationSettings};
+
+    #[test]
+    fn test_rng_is_seeded() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(7), draws(7));
+        assert_ne!(draws(7), draws(8));
+
+        let mut rng = Rng::new(0);
+        let samples: Vec<f64> = (0..10_000).map(|_| rng.next_normal()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.05);
+        assert!((0..1000).map(|_| rng.next_f64()).all(|x| (0.0..1.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_simulation() {
+        let reqs = parse_formations_text("Offense: RN\nDefense: DL\nRN=Spd\nDL=Spd\n").unwrap().remove(0).1;
+        let players = vec![player("Star", &[("Spd", 90.0)]), player("Backup", &[("Spd", 50.0)])];
+
+        // Without noise or absences the same player always starts
+        let steady = SimulationSettings { runs: 50, noise: 0.0, absence: 0.0, seed: 1 };
+        let result = simulate(&players, &reqs, &steady);
+        assert_eq!(result.starts, vec![("Star".to_string(), 50), ("Backup".to_string(), 0)]);
+        assert!(result.totals.iter().all(|t| *t == 180.0));
+
+        // A doubtful star misses about half the runs
+        let players = vec![Player { play_chance: 0.5, ..player("Star", &[("Spd", 90.0)]) }, player("Backup", &[("Spd", 50.0)])];
+        let noisy = SimulationSettings { runs: 400, noise: 0.05, absence: 0.0, seed: 3 };
+        let result = simulate(&players, &reqs, &noisy);
+        assert!((150..250).contains(&result.starts[0].1));
+        assert_eq!(result.starts[0].1 + result.starts[1].1, 400);
+        assert_eq!(result.totals.len(), 400);
+
+        let again = simulate(&players, &reqs, &noisy);
+        assert_eq!(again.totals, result.totals);
+    }
+}
+
+#[cfg(test)]
+mod sensitivity_tests {
+    use crate::composition::{formations_to_json, parse_formations_json};
+    use crate::evaluator::evaluate_in;
+    use super::fixtures::{parse_formations_text, player};
+    use crate::sensitivity::{sweep_constant, sweep_values};
+
+    #[test]
+    fn test_constants() {
+        let text = "const SPEED = 2\nOffense: RN\nDefense: DL\nRN=Spd * speed\nDL=Str\n";
+        let reqs = parse_formations_text(text).unwrap().remove(0).1;
+        assert_eq!(reqs.constants.get("SPEED"), Some(&2.0));
+
+        let runner = player("Runner", &[("Spd", 40.0), ("Str", 10.0)]);
+        assert_eq!(evaluate_in(&runner, "Spd * SPEED", &reqs).ok(), Some(80.0));
+
+        let json = formations_to_json(&[(String::new(), reqs)]).to_pretty_string();
+        let round_trip = parse_formations_json(&json).unwrap().remove(0).1;
+        assert_eq!(round_trip.constants.get("SPEED"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_sweep() {
+        assert_eq!(sweep_values(0.0, 1.0, Some(0.25)), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(sweep_values(0.5, 1.5, Some(0.1)).len(), 11);
+        assert_eq!(sweep_values(2.0, 1.0, Some(0.5)), vec![2.0, 1.5, 1.0]);
+
+        let text = "const SPEED = 1\nOffense: RN\nDefense: DL\nRN=Spd * SPEED\nDL=Str\n";
+        let reqs = parse_formations_text(text).unwrap().remove(0).1;
+        let players = vec![player("Runner", &[("Spd", 40.0), ("Str", 10.0)]), player("Lifter", &[("Spd", 10.0), ("Str", 45.0)])];
+
+        let points = sweep_constant(&players, &reqs, "speed", &[1.0, 2.0]).unwrap();
+        assert_eq!(points[0].starters[0].0, "Lifter");
+        assert_eq!(points[1].starters[0].0, "Runner");
+        assert_eq!(points[1].total, Some(90.0));
+
+        assert!(sweep_constant(&players, &reqs, "POWER", &[1.0]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod fitting_tests {
+    use crate::composition::PositionRequirements;
+    use crate::fitting::{fit_coefficients, fitted_formula, parse_fit_labels, Label};
+    use super::fixtures::player;
+
+    #[test]
+    fn test_parse_fit_labels() {
+        let text = "Formula: RN = a*Spd + b*(Agl + 1)\nFast > Slow  # comment\nSlow < Fast\nFast = 60\n";
+        let data = parse_fit_labels(text).unwrap();
+        assert_eq!(data.template.position, "RN");
+        assert_eq!(data.template.terms, vec![
+            ("a".to_string(), "Spd".to_string()),
+            ("b".to_string(), "(Agl + 1)".to_string()),
+        ]);
+        let prefer = Label::Prefer { better: "Fast".to_string(), worse: "Slow".to_string() };
+        assert_eq!(data.labels, vec![prefer.clone(), prefer, Label::Target { name: "Fast".to_string(), score: 60.0 }]);
+
+        assert_eq!(fitted_formula(&data.template, &[0.5, -0.25]), "0.5*Spd - 0.25*((Agl + 1))");
+
+        // A subtracted term is fitted on its negation and reads back as a subtraction
+        let data = parse_fit_labels("Formula: RN = a*Spd - b*Hnd + c*-Agl\nFast > Slow\n").unwrap();
+        assert_eq!(data.template.terms, vec![
+            ("a".to_string(), "Spd".to_string()),
+            ("b".to_string(), "-(Hnd)".to_string()),
+            ("c".to_string(), "-Agl".to_string()),
+        ]);
+        assert_eq!(fitted_formula(&data.template, &[0.5, 0.25, 0.1]), "0.5*Spd - 0.25*Hnd + 0.1*(-Agl)");
+
+        assert!(parse_fit_labels("Fast > Slow\n").is_err());
+        assert!(parse_fit_labels("Formula: RN = Spd + b*Agl\nFast > Slow\n").is_err());
+        assert!(parse_fit_labels("Formula: RN = a*Spd\nFast = quick\n").is_err());
+    }
+
+    #[test]
+    fn test_fit_coefficients() {
+        let players = vec![
+            player("A", &[("Spd", 50.0), ("Agl", 10.0)]), player("B", &[("Spd", 20.0), ("Agl", 40.0)]), player("C", &[("Spd", 30.0), ("Agl", 30.0)]), player("D", &[("Spd", 60.0), ("Agl", 60.0)]),
+        ];
+        let reqs = PositionRequirements::default();
+
+        // Targets from RN = 0.8*Spd + 0.2*Agl are recovered exactly
+        let text = "Formula: RN = a*Spd + b*Agl\nA = 42\nB = 24\nC = 30\nD = 60\n";
+        let result = fit_coefficients(&players, &reqs, &parse_fit_labels(text).unwrap()).unwrap();
+        assert!((result.coefficients[0] - 0.8).abs() < 1e-3);
+        assert!((result.coefficients[1] - 0.2).abs() < 1e-3);
+        assert!(result.target_rms.unwrap() < 1e-3);
+        assert!(result.converged);
+
+        // Preferences alone are matched, with the coefficients adding up to 1
+        let text = "Formula: RN = a*Spd + b*Agl\nB > A\nC > A\nD > C\n";
+        let result = fit_coefficients(&players, &reqs, &parse_fit_labels(text).unwrap()).unwrap();
+        assert!(result.unmatched.is_empty());
+        assert!((result.coefficients.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(result.coefficients[1] > result.coefficients[0]);
+        assert!(result.converged);
+
+        // Agl counts against the position: its coefficient on the negated
+        // stat comes out positive
+        let text = "Formula: RN = a*Spd - b*Agl\nA = 44\nB = -4\nC = 12\nD = 24\n";
+        let result = fit_coefficients(&players, &reqs, &parse_fit_labels(text).unwrap()).unwrap();
+        assert!((result.coefficients[0] - 1.0).abs() < 1e-3);
+        assert!((result.coefficients[1] - 0.6).abs() < 1e-3);
+
+        let text = "Formula: RN = a*Spd\nNobody > A\n";
+        assert!(fit_coefficients(&players, &reqs, &parse_fit_labels(text).unwrap()).is_err());
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::fixtures::{one_slot_reqs, player};
+    use crate::history::{
+        delta_snapshots, list_snapshots, progression, record_snapshot, stat_deltas, timestamp_label, Snapshot,
+        StatChange, StatDelta,
+    };
+
+    #[test]
+    fn test_timestamp_label() {
+        assert_eq!(timestamp_label(0), "1970-01-01_000000");
+        assert_eq!(timestamp_label(1_700_000_000), "2023-11-14_221320");
+        assert_eq!(timestamp_label(951_782_400), "2000-02-29_000000");
+
+        let snapshot = |label: &str| Snapshot { label: label.to_string(), path: String::new() };
+        let snapshots = vec![snapshot("2026-10-11_120000"), snapshot("2026-10-18_090000"), snapshot("2026-10-18_180000")];
+        let labels = |from: Option<&str>| delta_snapshots(&snapshots, from)
+            .map(|(from, to)| (from.label.as_str(), to.label.as_str()));
+        assert_eq!(labels(None), Ok(("2026-10-18_090000", "2026-10-18_180000")));
+        assert_eq!(labels(Some("2026-10-11")), Ok(("2026-10-11_120000", "2026-10-18_180000")));
+        // The prefix matches the latest, so it's compared with the one before
+        assert_eq!(labels(Some("2026-10-18")), Ok(("2026-10-18_090000", "2026-10-18_180000")));
+        assert_eq!(labels(Some("2025")), Err("No snapshot matches '2025'".to_string()));
+        assert!(delta_snapshots(&snapshots[..1], None).is_err());
+        assert!(delta_snapshots(&snapshots[..1], Some("2026")).is_err());
+        assert!(delta_snapshots(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_record_snapshot_order() {
+        let dir = std::env::temp_dir().join(format!("bb_team_picker_history_{}", std::process::id()));
+        let dir = dir.to_string_lossy().to_string();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // More copies than one digit holds, all likely within the same second
+        let recorded: Vec<String> = (0..12)
+            .map(|_| record_snapshot("testing/league/alpha.csv", &dir).unwrap().to_string_lossy().to_string())
+            .collect();
+        let listed: Vec<String> = list_snapshots(&dir).unwrap().into_iter().map(|s| s.path).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(listed, recorded);
+    }
+
+    #[test]
+    fn test_stat_deltas() {
+        let before = vec![player("Kept", &[("Spd", 40.0), ("Str", 50.0)]), player("Left", &[("Spd", 30.0), ("Str", 30.0)])];
+        let after = vec![player("Kept", &[("Spd", 43.0), ("Str", 50.0)]), player("Joined", &[("Spd", 20.0), ("Str", 20.0)])];
+
+        assert_eq!(stat_deltas(&before, &after), vec![
+            ("Kept".to_string(), StatChange::Changed(vec![("Spd".to_string(), StatDelta::By(3.0))])),
+            ("Joined".to_string(), StatChange::New),
+            ("Left".to_string(), StatChange::Gone),
+        ]);
+
+        // A stat going missing, coming back or appearing as a new column
+        let mut missing = player("Kept", &[("Spd", 40.0), ("Str", 50.0)]);
+        missing.stats.insert("Spd".to_string(), None);
+        let mut returned = player("Kept", &[("Spd", 44.0), ("Ag", 3.0)]);
+        returned.stats.insert("Str".to_string(), None);
+
+        assert_eq!(stat_deltas(std::slice::from_ref(&missing), &[returned]), vec![
+            ("Kept".to_string(), StatChange::Changed(vec![
+                ("Ag".to_string(), StatDelta::New(3.0)),
+                ("Spd".to_string(), StatDelta::New(44.0)),
+                ("Str".to_string(), StatDelta::NowMissing),
+            ])),
+        ]);
+        let dropped = player("Kept", &[("Spd", 40.0)]);
+        assert_eq!(stat_deltas(&before[..1], &[dropped]), vec![
+            ("Kept".to_string(), StatChange::Changed(vec![("Str".to_string(), StatDelta::NowMissing)])),
+        ]);
+    }
+
+    #[test]
+    fn test_progression() {
+        let reqs = one_slot_reqs();
+        let rosters = vec![
+            vec![player("Veteran", &[("Spd", 50.0), ("Str", 50.0)]), player("Rookie", &[("Spd", 30.0), ("Str", 30.0)])],
+            vec![player("Veteran", &[("Spd", 50.0), ("Str", 50.0)]), player("Rookie", &[("Spd", 60.0), ("Str", 60.0)])],
+        ];
+
+        let progress = progression(&rosters, &reqs);
+        assert_eq!(progress[0].name, "Rookie");
+
+        let started = |name: &str, i: usize| {
+            let player = progress.iter().find(|p| p.name == name).unwrap();
+            player.entries[i].as_ref().unwrap().started.is_some()
+        };
+        assert!(started("Veteran", 0) && !started("Rookie", 0));
+        assert!(!started("Veteran", 1) && started("Rookie", 1));
     }
-}
\ No newline at end of file
+}
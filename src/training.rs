@@ -0,0 +1,92 @@
+// src/training.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::pick_lineup;
+use crate::pick::{to_pick_data, PickTempData};
+use crate::player::Player;
+
+#[derive(Debug, Clone)]
+pub struct TrainingOption {
+    pub player: String,
+    pub stat: String,
+    pub gain: f64,
+}
+
+// Raises each stat of each player by `points`, re-optimizes the lineup and
+// records how much the team total moves. Best investments come first.
+pub fn recommend_training(
+    players: &[Player],
+    reqs: &PositionRequirements,
+    points: i32,
+) -> Vec<TrainingOption> {
+    let pick_data: Vec<PickTempData> = players.iter()
+        .map(|p| to_pick_data(p, reqs))
+        .collect();
+
+    let Some((_, baseline)) = pick_lineup(&pick_data, reqs) else {
+        return Vec::new();
+    };
+
+    let mut options = Vec::new();
+
+    for (i, player) in players.iter().enumerate() {
+        let mut stats: Vec<&String> = player.stats.keys().collect();
+        stats.sort();
+
+        for stat in stats {
+            let mut trained = player.clone();
+            // Missing stats have nothing to train from
+            match trained.stats.get_mut(stat) {
+                Some(Some(value)) => *value += points as f64,
+                _ => continue,
+            }
+
+            let trained_data = to_pick_data(&trained, reqs);
+
+            // Stats no formula looks at can't change anything.
+            if trained_data.position_scores == pick_data[i].position_scores {
+                continue;
+            }
+
+            let mut trial = pick_data.clone();
+            trial[i] = trained_data;
+
+            if let Some((_, total)) = pick_lineup(&trial, reqs) {
+                options.push(TrainingOption {
+                    player: player.name.clone(),
+                    stat: stat.clone(),
+                    gain: total - baseline,
+                });
+            }
+        }
+    }
+
+    options.sort_by(|a, b| b.gain.partial_cmp(&a.gain).unwrap());
+    options
+}
+
+pub fn print_training_report(options: &[TrainingOption], points: i32) {
+    let useful: Vec<&TrainingOption> = options.iter().filter(|o| o.gain > 0.005).collect();
+
+    if useful.is_empty() {
+        println!("No single +{} stat increase improves the team total.", points);
+        return;
+    }
+
+    let longest_name = useful.iter().map(|o| o.player.len()).max().unwrap_or(0);
+    let padding = 3;
+
+    println!("Training targets (+{} points)\n", points);
+    println!("{:<6}{:<name_width$}{:<8}Gain", "Rank", "Name", "Stat", name_width = longest_name + padding);
+
+    for (rank, option) in useful.iter().enumerate() {
+        println!(
+            "{:<6}{:<name_width$}{:<8}{:>+6.1}",
+            rank + 1,
+            option.player,
+            option.stat,
+            option.gain,
+            name_width = longest_name + padding
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// src/transfer.rs
+
+use crate::composition::PositionRequirements;
+use crate::lineup::pick_lineup;
+use crate::pick::{to_pick_data, PickTempData};
+use crate::player::Player;
+
+#[derive(Debug, Clone)]
+pub struct TransferOption {
+    pub name: String,
+    pub gain: f64,
+    pub displaced: Vec<String>,
+    pub price: Option<f64>,
+}
+
+impl TransferOption {
+    // Gain minus price, if the candidate has one.
+    pub fn net(&self) -> Option<f64> {
+        self.price.map(|price| self.gain - price)
+    }
+}
+
+// Adds each candidate to the roster in turn and reports how the optimized
+// team total changes and which starters they push out.
+pub fn evaluate_transfers(
+    players: &[Player],
+    candidates: &[Player],
+    reqs: &PositionRequirements,
+    price_column: Option<&str>,
+) -> Vec<TransferOption> {
+    let pick_data: Vec<PickTempData> = players.iter()
+        .map(|p| to_pick_data(p, reqs))
+        .collect();
+
+    let Some((baseline_lineup, baseline)) = pick_lineup(&pick_data, reqs) else {
+        return Vec::new();
+    };
+
+    let mut options = Vec::new();
+
+    // The candidate goes after the roster, so rostered players keep their
+    // indices and a namesake can't be mistaken for the candidate
+    let candidate_index = pick_data.len();
+
+    for candidate in candidates {
+        let mut trial = pick_data.clone();
+        trial.push(to_pick_data(candidate, reqs));
+
+        let Some((lineup, total)) = pick_lineup(&trial, reqs) else {
+            continue;
+        };
+
+        let displaced = if lineup.iter().any(|s| s.index == candidate_index) {
+            baseline_lineup.iter()
+                .filter(|old| !lineup.iter().any(|new| new.index == old.index))
+                .map(|old| old.name.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let price = price_column.and_then(|column| {
+            candidate.stats.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(column))
+                .and_then(|(_, value)| *value)
+        });
+
+        options.push(TransferOption {
+            name: candidate.name.clone(),
+            gain: total - baseline,
+            displaced,
+            price,
+        });
+    }
+
+    // Best net value first. Candidates without a price can't be netted, so
+    // with a price column they come after the priced ones, best gain first.
+    options.sort_by(|a, b| {
+        a.price.is_none().cmp(&b.price.is_none())
+            .then_with(|| b.net().unwrap_or(b.gain).total_cmp(&a.net().unwrap_or(a.gain)))
+    });
+    options
+}
+
+pub fn print_transfer_report(options: &[TransferOption], price_column: Option<&str>) {
+    if options.is_empty() {
+        println!("No candidates to evaluate.");
+        return;
+    }
+
+    let longest_name = options.iter().map(|o| o.name.len()).max().unwrap_or(0);
+    let padding = 3;
+
+    println!("Transfer candidates\n");
+
+    match price_column {
+        Some(column) => println!(
+            "{:<name_width$}{:>6}{:>8}{:>8}   Displaces",
+            "Name", "Gain", column, "Net",
+            name_width = longest_name + padding
+        ),
+        None => println!(
+            "{:<name_width$}{:>6}   Displaces",
+            "Name", "Gain",
+            name_width = longest_name + padding
+        ),
+    }
+
+    for option in options {
+        let displaced = if option.displaced.is_empty() {
+            "-".to_string()
+        } else {
+            option.displaced.join(", ")
+        };
+
+        match price_column {
+            Some(_) => {
+                let price = option.price.map(|p| format!("{:.0}", p)).unwrap_or_else(|| "?".to_string());
+                let net = option.net().map(|n| format!("{:+.1}", n)).unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{:<name_width$}{:>+6.1}{:>8}{:>8}   {}",
+                    option.name,
+                    option.gain,
+                    price,
+                    net,
+                    displaced,
+                    name_width = longest_name + padding
+                );
+            }
+            None => println!(
+                "{:<name_width$}{:>+6.1}   {}",
+                option.name,
+                option.gain,
+                displaced,
+                name_width = longest_name + padding
+            ),
+        }
+    }
+}